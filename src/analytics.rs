@@ -0,0 +1,521 @@
+//! Calculations derived from Lean's chart series. Series are `(x, y)` points sorted by x,
+//! where x is a Unix timestamp in seconds.
+
+use chrono::Datelike;
+use serde::Deserialize;
+
+/// Value of `series` at `x`, taken from the last point at or before it. `None` if `x` is
+/// before the first point.
+pub fn value_at(series: &[(f64, f64)], x: f64) -> Option<f64> {
+    match series.partition_point(|p| p.0 <= x) {
+        0 => None,
+        i => Some(series[i - 1].1)
+    }
+}
+
+/// Scales `series` so that it's worth `base_value` at `base_x`, making it comparable to
+/// another series starting from that value. A series that starts after `base_x` is based at
+/// its first point instead.
+pub fn rebase(series: &[(f64, f64)], base_x: f64, base_value: f64) -> Vec<(f64, f64)> {
+    let start = value_at(series, base_x).or_else(|| series.first().map(|p| p.1));
+    match start {
+        Some(start) if start > 0.0 => {
+            let factor = base_value / start;
+            series.iter().map(|&(x, y)| (x, y * factor)).collect()
+        },
+        _ => vec![]
+    }
+}
+
+/// Percent drawdown from the running peak at every point of an equity series, e.g. `-12.5`
+pub fn drawdown(equity: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let mut peak = f64::NEG_INFINITY;
+    equity
+        .iter()
+        .map(|&(x, y)| {
+            peak = peak.max(y);
+            (x, if peak > 0.0 { (y / peak - 1.0) * 100.0 } else { 0.0 })
+        })
+        .collect()
+}
+
+/// Summary of a percent drawdown series
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrawdownStats {
+    /// Deepest drawdown, as a negative percentage
+    pub max: f64,
+    /// Time of the peak the deepest drawdown started from
+    pub start: f64,
+    /// Time of the deepest point
+    pub trough: f64,
+    /// First time the peak was regained, `None` if it hasn't been yet
+    pub recovery: Option<f64>,
+    /// Drawdown at the last point
+    pub current: f64,
+}
+
+impl DrawdownStats {
+    pub fn new(drawdown: &[(f64, f64)]) -> Option<Self> {
+        let last = drawdown.last()?;
+        let (trough_index, &(trough, max)) = drawdown
+            .iter()
+            .enumerate()
+            .min_by(|a, b| (a.1).1.partial_cmp(&(b.1).1).unwrap())?;
+
+        // Lean's own series rounds, so anything within a hundredth of a percent is at the peak
+        let at_peak = |dd: f64| dd >= -0.01;
+        let start = drawdown[..=trough_index]
+            .iter()
+            .rev()
+            .find(|p| at_peak(p.1))
+            .map(|p| p.0)
+            .unwrap_or(drawdown[0].0);
+        let recovery = drawdown[trough_index..]
+            .iter()
+            .find(|p| at_peak(p.1))
+            .map(|p| p.0);
+
+        Some(Self {
+            max,
+            start,
+            trough,
+            recovery,
+            current: last.1
+        })
+    }
+}
+
+/// Calendar period that returns are aggregated over
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReturnPeriod {
+    #[default]
+    Daily,
+    Weekly,
+    Monthly
+}
+
+impl ReturnPeriod {
+    pub fn next(&self) -> Self {
+        match self {
+            ReturnPeriod::Daily => ReturnPeriod::Weekly,
+            ReturnPeriod::Weekly => ReturnPeriod::Monthly,
+            ReturnPeriod::Monthly => ReturnPeriod::Daily
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ReturnPeriod::Daily => "Daily",
+            ReturnPeriod::Weekly => "Weekly",
+            ReturnPeriod::Monthly => "Monthly"
+        }
+    }
+
+    /// Identifies the period a timestamp falls in, in UTC
+    fn key(&self, x: f64) -> (i32, u32) {
+        let date = match chrono::DateTime::from_timestamp(x as i64, 0) {
+            Some(time) => time.date_naive(),
+            None => return (0, 0)
+        };
+
+        match self {
+            ReturnPeriod::Daily => (date.year(), date.ordinal()),
+            ReturnPeriod::Weekly => (date.iso_week().year(), date.iso_week().week()),
+            ReturnPeriod::Monthly => (date.year(), date.month())
+        }
+    }
+}
+
+/// Last point of every calendar period. Lean's chart points are irregularly spaced (sampled
+/// daily in backtests, more often live), so this puts them on a common footing.
+pub fn resample(series: &[(f64, f64)], period: ReturnPeriod) -> Vec<(f64, f64)> {
+    let mut closes: Vec<(f64, f64)> = Vec::new();
+    let mut last_key = None;
+
+    for &(x, y) in series {
+        let key = period.key(x);
+        if last_key == Some(key) {
+            *closes.last_mut().unwrap() = (x, y);
+        } else {
+            closes.push((x, y));
+            last_key = Some(key);
+        }
+    }
+
+    closes
+}
+
+/// Percent returns between consecutive closes, stamped with the time of the later close
+pub fn returns(closes: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    closes
+        .windows(2)
+        .filter(|w| w[0].1 != 0.0)
+        .map(|w| (w[1].0, (w[1].1 / w[0].1 - 1.0) * 100.0))
+        .collect()
+}
+
+/// Mean, standard deviation, skew and excess kurtosis of a sample
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Moments {
+    pub count: usize,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub skew: f64,
+    pub kurtosis: f64,
+}
+
+impl Moments {
+    pub fn new(values: &[f64]) -> Option<Self> {
+        if values.is_empty() {
+            return None
+        }
+
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let central = |power: i32| values.iter().map(|v| (v - mean).powi(power)).sum::<f64>() / n;
+        let variance = central(2);
+        let std_dev = variance.sqrt();
+
+        let (skew, kurtosis) = if variance > 0.0 {
+            (central(3) / std_dev.powi(3), central(4) / variance.powi(2) - 3.0)
+        } else {
+            (0.0, 0.0)
+        };
+
+        Some(Self {
+            count: values.len(),
+            mean,
+            std_dev,
+            skew,
+            kurtosis
+        })
+    }
+}
+
+/// Counts `values` into buckets of `width` aligned on multiples of `width`. Returns the lower
+/// edge and count of every bucket from the lowest to the highest value, empty ones included.
+pub fn histogram(values: &[f64], width: f64) -> Vec<(f64, u64)> {
+    if values.is_empty() || width <= 0.0 {
+        return vec![]
+    }
+
+    let bucket = |v: f64| (v / width).floor() as i64;
+    let lowest = values.iter().map(|&v| bucket(v)).min().unwrap();
+    let highest = values.iter().map(|&v| bucket(v)).max().unwrap();
+
+    let mut counts = vec![0u64; (highest - lowest + 1) as usize];
+    for &v in values {
+        counts[(bucket(v) - lowest) as usize] += 1;
+    }
+
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| ((lowest + i as i64) as f64 * width, count))
+        .collect()
+}
+
+/// Percent returns of every month of one calendar year, plus the compounded year total
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct YearReturns {
+    pub year: i32,
+    /// January first, `None` for months outside of the backtest
+    pub months: [Option<f64>; 12],
+    pub total: f64,
+}
+
+/// Month names heading monthly returns tables
+pub const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+/// Monthly returns of an equity series grouped by year. The first month is measured from the
+/// first point, so a backtest starting mid-month still gets a return for it.
+pub fn monthly_returns(equity: &[(f64, f64)]) -> Vec<YearReturns> {
+    let first = match equity.first() {
+        Some(&first) => first,
+        None => return vec![]
+    };
+
+    let mut closes = vec![first];
+    closes.extend(resample(equity, ReturnPeriod::Monthly));
+
+    let mut years: Vec<YearReturns> = Vec::new();
+    for (x, pct) in returns(&closes) {
+        let (year, month) = ReturnPeriod::Monthly.key(x);
+        if month == 0 {
+            continue
+        }
+        if years.last().map(|y| y.year) != Some(year) {
+            years.push(YearReturns { year, ..Default::default() });
+        }
+
+        let current = years.last_mut().unwrap();
+        current.months[month as usize - 1] = Some(pct);
+        current.total = ((1.0 + current.total / 100.0) * (1.0 + pct / 100.0) - 1.0) * 100.0;
+    }
+
+    years
+}
+
+/// Trading days in a year, used to annualize daily statistics
+pub const TRADING_DAYS: f64 = 252.0;
+
+/// Risk statistic shown by the rolling metrics chart, as written in the config file
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RiskMetric {
+    #[default]
+    Sharpe,
+    Volatility,
+    Beta
+}
+
+impl RiskMetric {
+    pub fn next(&self) -> Self {
+        match self {
+            RiskMetric::Sharpe => RiskMetric::Volatility,
+            RiskMetric::Volatility => RiskMetric::Beta,
+            RiskMetric::Beta => RiskMetric::Sharpe
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RiskMetric::Sharpe => "Sharpe",
+            RiskMetric::Volatility => "Volatility",
+            RiskMetric::Beta => "Beta"
+        }
+    }
+
+    /// Value of the metric over one window of daily strategy and benchmark returns
+    fn measure(&self, strategy: &[f64], benchmark: &[f64]) -> Option<f64> {
+        let n = strategy.len() as f64;
+        let mean = |values: &[f64]| values.iter().sum::<f64>() / n;
+        let (s_mean, b_mean) = (mean(strategy), mean(benchmark));
+        let s_var = strategy.iter().map(|r| (r - s_mean).powi(2)).sum::<f64>() / n;
+
+        match self {
+            RiskMetric::Sharpe if s_var > 0.0 => Some(s_mean / s_var.sqrt() * TRADING_DAYS.sqrt()),
+            RiskMetric::Volatility => Some(s_var.sqrt() * TRADING_DAYS.sqrt() * 100.0),
+            RiskMetric::Beta => {
+                let b_var = benchmark.iter().map(|r| (r - b_mean).powi(2)).sum::<f64>() / n;
+                let covariance = strategy
+                    .iter()
+                    .zip(benchmark)
+                    .map(|(s, b)| (s - s_mean) * (b - b_mean))
+                    .sum::<f64>() / n;
+
+                if b_var > 0.0 { Some(covariance / b_var) } else { None }
+            },
+            _ => None
+        }
+    }
+}
+
+/// `metric` over every `window` consecutive daily returns of `equity`, stamped with the last
+/// day of the window. Sharpe assumes a risk-free rate of zero and volatility is annualized
+/// in percent. Beta is measured against `benchmark` on the same days.
+pub fn rolling(equity: &[(f64, f64)], benchmark: &[(f64, f64)], window: usize, metric: RiskMetric) -> Vec<(f64, f64)> {
+    if window < 2 || (metric == RiskMetric::Beta && benchmark.is_empty()) {
+        return vec![]
+    }
+
+    let closes = resample(equity, ReturnPeriod::Daily);
+    let benchmark = closes
+        .iter()
+        .map(|&(x, _)| value_at(benchmark, x).unwrap_or(0.0))
+        .collect::<Vec<f64>>();
+
+    let mut times = Vec::with_capacity(closes.len());
+    let mut strategy = Vec::with_capacity(closes.len());
+    let mut market = Vec::with_capacity(closes.len());
+    for (s, b) in closes.windows(2).zip(benchmark.windows(2)) {
+        if s[0].1 == 0.0 || (metric == RiskMetric::Beta && b[0] == 0.0) {
+            continue
+        }
+
+        times.push(s[1].0);
+        strategy.push(s[1].1 / s[0].1 - 1.0);
+        market.push(if b[0] != 0.0 { b[1] / b[0] - 1.0 } else { 0.0 });
+    }
+
+    if strategy.len() < window {
+        return vec![]
+    }
+
+    (window..=strategy.len())
+        .filter_map(|end| {
+            let start = end - window;
+            metric
+                .measure(&strategy[start..end], &market[start..end])
+                .map(|value| (times[end - 1], value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::assert_close;
+
+    fn time(year: i32, month: u32, day: u32) -> f64 {
+        chrono::NaiveDate::from_ymd_opt(year, month, day).unwrap().and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp() as f64
+    }
+
+    #[test]
+    fn value_at_steps_between_points() {
+        let series = [(10.0, 1.0), (20.0, 2.0), (30.0, 3.0)];
+        assert_eq!(value_at(&series, 5.0), None);
+        assert_eq!(value_at(&series, 10.0), Some(1.0));
+        assert_eq!(value_at(&series, 25.0), Some(2.0));
+        assert_eq!(value_at(&series, 99.0), Some(3.0));
+        assert_eq!(value_at(&[], 10.0), None);
+    }
+
+    #[test]
+    fn rebase_to_base_value() {
+        let benchmark = [(10.0, 50.0), (20.0, 55.0), (30.0, 45.0)];
+        assert_eq!(rebase(&benchmark, 20.0, 1100.0), vec![(10.0, 1000.0), (20.0, 1100.0), (30.0, 900.0)]);
+        // Before the benchmark starts it's based at its first point
+        assert_eq!(rebase(&benchmark, 0.0, 100.0), vec![(10.0, 100.0), (20.0, 110.0), (30.0, 90.0)]);
+
+        assert!(rebase(&[(10.0, 0.0), (20.0, 5.0)], 10.0, 100.0).is_empty());
+        assert!(rebase(&[], 10.0, 100.0).is_empty());
+    }
+
+    #[test]
+    fn drawdown_from_running_peak() {
+        let equity = [(1.0, 100.0), (2.0, 120.0), (3.0, 90.0), (4.0, 108.0), (5.0, 120.0), (6.0, 130.0)];
+        let drawdown = drawdown(&equity);
+
+        let expected = [0.0, 0.0, -25.0, -10.0, 0.0, 0.0];
+        assert_eq!(drawdown.iter().map(|p| p.0).collect::<Vec<f64>>(), equity.iter().map(|p| p.0).collect::<Vec<f64>>());
+        for (&(_, actual), &expected) in drawdown.iter().zip(expected.iter()) {
+            assert_close(actual, expected);
+        }
+    }
+
+    #[test]
+    fn drawdown_stats_start_trough_and_recovery() {
+        let equity = [(1.0, 100.0), (2.0, 120.0), (3.0, 90.0), (4.0, 108.0), (5.0, 120.0), (6.0, 130.0)];
+        let stats = DrawdownStats::new(&drawdown(&equity)).unwrap();
+
+        assert_close(stats.max, -25.0);
+        assert_eq!(stats.start, 2.0);
+        assert_eq!(stats.trough, 3.0);
+        assert_eq!(stats.recovery, Some(5.0));
+        assert_close(stats.current, 0.0);
+    }
+
+    #[test]
+    fn drawdown_stats_not_recovered() {
+        let stats = DrawdownStats::new(&drawdown(&[(1.0, 100.0), (2.0, 90.0), (3.0, 80.0)])).unwrap();
+
+        assert_close(stats.max, -20.0);
+        assert_eq!(stats.start, 1.0);
+        assert_eq!(stats.trough, 3.0);
+        assert_eq!(stats.recovery, None);
+        assert_close(stats.current, -20.0);
+        assert_eq!(DrawdownStats::new(&[]), None);
+    }
+
+    #[test]
+    fn histogram_bucket_edges() {
+        // Lower edges are inclusive, so values on an edge land in the bucket above it
+        let buckets = histogram(&[0.0, 0.99, 1.0, -0.01, -1.0], 1.0);
+        assert_eq!(buckets, vec![(-1.0, 2), (0.0, 2), (1.0, 1)]);
+
+        let buckets = histogram(&[0.5, 2.5], 1.0);
+        assert_eq!(buckets, vec![(0.0, 1), (1.0, 0), (2.0, 1)]);
+
+        assert!(histogram(&[], 1.0).is_empty());
+        assert!(histogram(&[1.0], 0.0).is_empty());
+    }
+
+    #[test]
+    fn moments_of_a_sample() {
+        let moments = Moments::new(&[1.0, 2.0, 3.0, 4.0]).unwrap();
+        assert_eq!(moments.count, 4);
+        assert_close(moments.mean, 2.5);
+        assert_close(moments.std_dev, 1.25f64.sqrt());
+        assert_close(moments.skew, 0.0);
+        assert_close(moments.kurtosis, 2.5625 / 1.5625 - 3.0);
+
+        // Without spread the higher moments are undefined and reported as 0
+        let flat = Moments::new(&[2.0, 2.0, 2.0]).unwrap();
+        assert_close(flat.std_dev, 0.0);
+        assert_close(flat.skew, 0.0);
+        assert_close(flat.kurtosis, 0.0);
+        assert_eq!(Moments::new(&[]), None);
+    }
+
+    #[test]
+    fn monthly_returns_mid_month_start() {
+        let equity = [
+            (time(2020, 1, 15), 100.0),
+            (time(2020, 1, 20), 105.0),
+            (time(2020, 1, 31), 110.0),
+            (time(2020, 2, 28), 99.0)
+        ];
+        let years = monthly_returns(&equity);
+
+        assert_eq!(years.len(), 1);
+        assert_eq!(years[0].year, 2020);
+        assert_close(years[0].months[0].unwrap(), 10.0);
+        assert_close(years[0].months[1].unwrap(), -10.0);
+        assert!(years[0].months[2..].iter().all(|m| m.is_none()));
+        assert_close(years[0].total, -1.0);
+    }
+
+    #[test]
+    fn monthly_returns_across_years() {
+        let equity = [(time(2020, 12, 15), 100.0), (time(2020, 12, 31), 110.0), (time(2021, 1, 29), 121.0)];
+        let years = monthly_returns(&equity);
+
+        assert_eq!(years.iter().map(|y| y.year).collect::<Vec<i32>>(), vec![2020, 2021]);
+        assert_close(years[0].months[11].unwrap(), 10.0);
+        assert_close(years[0].total, 10.0);
+        assert_close(years[1].months[0].unwrap(), 10.0);
+        assert!(monthly_returns(&[]).is_empty());
+    }
+
+    #[test]
+    fn risk_metrics_of_a_window() {
+        let (strategy, benchmark) = ([0.02, 0.0], [0.04, 0.0]);
+        assert_close(RiskMetric::Sharpe.measure(&strategy, &benchmark).unwrap(), TRADING_DAYS.sqrt());
+        assert_close(RiskMetric::Volatility.measure(&strategy, &benchmark).unwrap(), TRADING_DAYS.sqrt());
+        assert_close(RiskMetric::Beta.measure(&strategy, &benchmark).unwrap(), 0.5);
+
+        // Without variance Sharpe and beta are undefined
+        let flat = [0.01, 0.01];
+        assert_eq!(RiskMetric::Sharpe.measure(&flat, &flat), None);
+        assert_eq!(RiskMetric::Beta.measure(&strategy, &flat), None);
+        assert_close(RiskMetric::Volatility.measure(&flat, &flat).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn rolling_constant_returns() {
+        // Doubling every day gives exactly the same return each day
+        let equity = (0..6)
+            .map(|i| (time(2020, 1, 1 + i), 2f64.powi(i as i32)))
+            .collect::<Vec<(f64, f64)>>();
+
+        let volatility = rolling(&equity, &[], 3, RiskMetric::Volatility);
+        assert_eq!(volatility.len(), 3);
+        assert_eq!(volatility[0].0, time(2020, 1, 4));
+        assert!(volatility.iter().all(|&(_, v)| v == 0.0));
+
+        assert!(rolling(&equity, &[], 3, RiskMetric::Sharpe).is_empty());
+        assert!(rolling(&equity, &equity, 3, RiskMetric::Beta).is_empty());
+        assert!(rolling(&equity, &[], 3, RiskMetric::Beta).is_empty());
+    }
+
+    #[test]
+    fn rolling_window_longer_than_data() {
+        let equity = [(time(2020, 1, 1), 100.0), (time(2020, 1, 2), 101.0), (time(2020, 1, 3), 99.0)];
+        assert!(rolling(&equity, &[], 3, RiskMetric::Volatility).is_empty());
+        assert_eq!(rolling(&equity, &[], 2, RiskMetric::Volatility).len(), 1);
+        assert!(rolling(&equity, &[], 1, RiskMetric::Volatility).is_empty());
+        assert!(rolling(&[], &[], 21, RiskMetric::Sharpe).is_empty());
+    }
+}
@@ -0,0 +1,150 @@
+use serde::Deserialize;
+
+use crate::{analytics::{ReturnPeriod, RiskMetric}, portfolio::Matching, report::ReportFormat, terminal::{rules::HighlightRule, theme::ThemePreset}};
+
+/// Environment variable that can point to a config file outside of the working directory
+pub const CONFIG_PATH_ENV: &str = "LEAN_TUI_CONFIG";
+
+/// Default config file, relative to the directory Lean is launched from
+pub const DEFAULT_CONFIG_PATH: &str = "lean_tui.json";
+
+/// User configuration, read once when the terminal is initialized
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Color scheme used by every renderer
+    pub theme: ThemePreset,
+    /// Returns histogram on the stats page
+    pub histogram: HistogramConfig,
+    /// Rolling risk metrics chart on the charts page
+    pub rolling: RollingConfig,
+    /// How exits are paired with entries on the trades page
+    pub trade_matching: Matching,
+    /// How many log lines are kept in memory and where older ones go
+    pub logs: LogConfig,
+    /// Styles and actions for log lines matching a pattern, the first styled match winning
+    pub highlights: Vec<HighlightRule>,
+    /// Where orders and chart series are written as CSV
+    pub export: ExportConfig,
+    /// Report written when the run stops
+    pub report: ReportConfig,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct HistogramConfig {
+    /// Width of a bucket, in percent
+    pub bucket_width: f64,
+    /// Calendar period returns are aggregated over
+    pub period: ReturnPeriod,
+}
+
+impl Default for HistogramConfig {
+    fn default() -> Self {
+        Self {
+            bucket_width: 0.5,
+            period: ReturnPeriod::Daily
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct RollingConfig {
+    /// Number of daily returns in each window, e.g. 21, 63 or 252
+    pub window: usize,
+    /// Metric shown when the terminal starts
+    pub metric: RiskMetric,
+}
+
+impl Default for RollingConfig {
+    fn default() -> Self {
+        Self {
+            window: 63,
+            metric: RiskMetric::Sharpe
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+    /// Number of log lines kept in memory before the oldest are written to disk
+    pub capacity: usize,
+    /// Spill files are named `<spill_path>.<n>.log`. Relative paths are resolved against the
+    /// directory Lean runs from, so by default they're `lean_tui_logs.0.log`,
+    /// `lean_tui_logs.1.log` and so on there. They're deleted when the run stops.
+    pub spill_path: String,
+    /// Size at which a spill file is closed and the next one started
+    pub max_file_bytes: u64,
+    /// Number of spill files kept, the oldest being deleted first
+    pub max_files: usize,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 10_000,
+            spill_path: "lean_tui_logs".to_string(),
+            max_file_bytes: 16 * 1024 * 1024,
+            max_files: 4
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ExportConfig {
+    /// Directory the CSV files are written to, created if it doesn't exist
+    pub directory: String,
+    /// Whether to export when Lean frees the terminal. Lean can also turn this on through
+    /// `export_on_free`.
+    pub on_free: bool,
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        Self {
+            directory: "lean_tui_export".to_string(),
+            on_free: false
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ReportConfig {
+    /// `markdown`, `html` or `none` to not write a report
+    pub format: ReportFormat,
+    /// Report file, without the extension the format adds
+    pub path: String,
+}
+
+impl Default for ReportConfig {
+    fn default() -> Self {
+        Self {
+            format: ReportFormat::None,
+            path: "lean_tui_report".to_string()
+        }
+    }
+}
+
+impl Config {
+    /// Loads the config from `$LEAN_TUI_CONFIG` or `lean_tui.json`, falling back to the defaults
+    /// if the file doesn't exist. Parse errors are written to disk since stdout belongs to the TUI.
+    pub fn load() -> Self {
+        let path = std::env::var(CONFIG_PATH_ENV).unwrap_or_else(|_| DEFAULT_CONFIG_PATH.to_string());
+        let raw = match std::fs::read_to_string(&path) {
+            Ok(raw) => raw,
+            Err(_) => return Self::default()
+        };
+
+        match serde_json::from_str::<Config>(&raw) {
+            Ok(config) => config,
+            Err(err) => {
+                std::fs::write("lean_tui_config_error.log", format!("{}: {:?}", path, err)).unwrap();
+                Self::default()
+            }
+        }
+    }
+}
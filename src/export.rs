@@ -0,0 +1,153 @@
+use std::{borrow::Cow, collections::BTreeMap, fs, io, path::{Path, PathBuf}};
+
+use crate::model::Order;
+
+/// Points of every series of every chart, by chart name and then series name
+pub type ChartSeries = BTreeMap<String, BTreeMap<String, Vec<(f64, f64)>>>;
+
+/// Name of the orders file inside the export directory
+pub const ORDERS_FILE: &str = "orders.csv";
+
+/// Name of the chart series file inside the export directory
+pub const CHARTS_FILE: &str = "charts.csv";
+
+/// Writes the orders and chart series as CSV files into `directory`, creating it if needed
+/// and replacing earlier exports. Returns the paths written.
+pub fn export(directory: &Path, orders: &[Order], charts: &ChartSeries) -> io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(directory)?;
+
+    let orders_path = directory.join(ORDERS_FILE);
+    fs::write(&orders_path, orders_csv(orders))?;
+
+    let charts_path = directory.join(CHARTS_FILE);
+    fs::write(&charts_path, charts_csv(charts))?;
+
+    Ok(vec![orders_path, charts_path])
+}
+
+/// One row per order with every field Lean sends. The symbol is split into its parts, broker
+/// ids are joined with `;` and nested objects such as `Properties` are written as JSON.
+pub fn orders_csv(orders: &[Order]) -> String {
+    let mut csv = String::from(
+        "Id,ContingentId,BrokerId,Symbol,SymbolId,Permtick,Price,PriceCurrency,Time,CreatedTime,LastFillTime,\
+        LastUpdateTime,CanceledTime,Quantity,Type,Status,TimeInForce,Tag,Properties,SecurityType,Direction,Value,\
+        OrderSubmissionData,IsMarketable,LimitPrice,StopPrice,StopTriggered\n"
+    );
+
+    for order in orders {
+        let fields = [
+            order.Id.to_string(),
+            order.ContingentId.to_string(),
+            order.BrokerId.join(";"),
+            order.Symbol.Value.clone(),
+            order.Symbol.ID.clone(),
+            order.Symbol.Permtick.clone(),
+            order.Price.to_string(),
+            order.PriceCurrency.clone(),
+            order.Time.clone(),
+            order.CreatedTime.clone(),
+            order.LastFillTime.clone().unwrap_or_default(),
+            order.LastUpdateTime.clone().unwrap_or_default(),
+            order.CanceledTime.clone().unwrap_or_default(),
+            order.Quantity.to_string(),
+            order.Type.to_string(),
+            order.Status.to_string(),
+            json(&order.TimeInForce),
+            order.Tag.clone().unwrap_or_default(),
+            json(&order.Properties),
+            order.SecurityType.to_string(),
+            order.Direction.to_string(),
+            order.Value.to_string(),
+            json(&order.OrderSubmissionData),
+            order.IsMarketable.to_string(),
+            optional(order.LimitPrice),
+            optional(order.StopPrice),
+            optional(order.StopTriggered),
+        ];
+
+        push_row(&mut csv, &fields);
+    }
+
+    csv
+}
+
+/// Every point in long format, so series with different timestamps share one file. Time
+/// is given both as Lean's unix seconds and in UTC.
+pub fn charts_csv(charts: &ChartSeries) -> String {
+    let mut csv = String::from("Chart,Series,Time,Unix,Value\n");
+    for (chart, series) in charts {
+        for (name, points) in series {
+            for (x, y) in points {
+                let time = chrono::DateTime::from_timestamp(*x as i64, 0)
+                    .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+                    .unwrap_or_default();
+
+                push_row(&mut csv, &[chart.clone(), name.clone(), time, x.to_string(), y.to_string()]);
+            }
+        }
+    }
+
+    csv
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// Compact JSON of a nested object, empty when Lean left it out or sent null
+fn json(value: &Option<serde_json::Value>) -> String {
+    match value {
+        Some(serde_json::Value::Null) | None => String::new(),
+        Some(value) => value.to_string()
+    }
+}
+
+fn push_row(csv: &mut String, fields: &[String]) {
+    let row = fields.iter().map(|field| escape(field)).collect::<Vec<Cow<str>>>();
+    csv.push_str(&row.join(","));
+    csv.push('\n');
+}
+
+/// Quotes a field if it contains a separator, quote or line break (RFC 4180)
+fn escape(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORDER: &str = r#"{"Id":1,"ContingentId":0,"BrokerId":["a","b"],"Symbol":{"Value":"SPY","ID":"SPY R735QTJ8XC9X","Permtick":"SPY"},
+        "Price":10.5,"PriceCurrency":"USD","Time":"2020-01-02T14:31:00Z","CreatedTime":"2020-01-02T14:31:00Z","LastFillTime":null,
+        "LastUpdateTime":null,"CanceledTime":null,"Quantity":-3,"Type":1,"Status":3,"TimeInForce":{},"Tag":"say \"hi\", twice",
+        "Properties":{"TimeInForce":{}},"SecurityType":1,"Direction":1,"Value":-31.5,"OrderSubmissionData":null,"IsMarketable":true,
+        "LimitPrice":10.5,"StopPrice":null,"StopTriggered":null}"#;
+
+    #[test]
+    fn orders_csv_writes_every_field() {
+        let order: Order = serde_json::from_str(ORDER).unwrap();
+        let csv = orders_csv(&[order]);
+        let mut lines = csv.lines();
+
+        let header = lines.next().unwrap();
+        assert_eq!(header.split(',').count(), 27);
+        assert_eq!(
+            lines.next().unwrap(),
+            "1,0,a;b,SPY,SPY R735QTJ8XC9X,SPY,10.5,USD,2020-01-02T14:31:00Z,2020-01-02T14:31:00Z,,,,-3,1,3,{},\
+            \"say \"\"hi\"\", twice\",\"{\"\"TimeInForce\"\":{}}\",1,1,-31.5,,true,10.5,,"
+        );
+        assert_eq!(lines.next(), None);
+    }
+
+    #[test]
+    fn charts_csv_long_format() {
+        let mut charts = ChartSeries::new();
+        charts.entry("Strategy Equity".to_string()).or_default().insert("Equity".to_string(), vec![(1577975460.0, 100000.0)]);
+
+        assert_eq!(charts_csv(&charts), "Chart,Series,Time,Unix,Value\nStrategy Equity,Equity,2020-01-02 14:31:00,1577975460,100000\n");
+    }
+}
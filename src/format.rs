@@ -0,0 +1,130 @@
+/// Account currency used until Lean tells us otherwise
+pub const DEFAULT_ACCOUNT_CURRENCY: &str = "USD";
+
+/// Symbol Lean displays for a currency code, or the code itself followed by a space
+pub fn currency_symbol(code: &str) -> String {
+    let symbol = match code {
+        "USD" | "AUD" | "CAD" | "HKD" | "NZD" | "SGD" => "$",
+        "EUR" => "€",
+        "GBP" => "£",
+        "JPY" | "CNY" | "CNH" => "¥",
+        "INR" => "₹",
+        "KRW" => "₩",
+        "CHF" => "CHF ",
+        "BTC" => "₿",
+        "ETH" => "Ξ",
+        _ => return format!("{} ", code)
+    };
+
+    symbol.to_string()
+}
+
+/// Formats `value` with `decimals` decimal places and comma thousands separators
+pub fn thousands(value: f64, decimals: usize) -> String {
+    let formatted = format!("{:.*}", decimals, value.abs());
+    let (integer, fraction) = match formatted.find('.') {
+        Some(i) => formatted.split_at(i),
+        None => (formatted.as_str(), "")
+    };
+
+    let mut grouped = String::with_capacity(integer.len() + integer.len() / 3);
+    for (i, c) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+
+    let sign = if value < 0.0 && formatted.chars().any(|c| c != '0' && c != '.') { "-" } else { "" };
+    format!("{}{}{}", sign, grouped, fraction)
+}
+
+/// Full precision currency amount, e.g. `-$1,234.56`
+pub fn currency(value: f64, symbol: &str) -> String {
+    let amount = thousands(value, 2);
+    match amount.strip_prefix('-') {
+        Some(amount) => format!("-{}{}", symbol, amount),
+        None => format!("{}{}", symbol, amount)
+    }
+}
+
+/// Compact currency amount for axis labels, e.g. `$1.25M`. `step` is the distance between
+/// neighbouring labels and decides how many decimals are needed to tell them apart.
+pub fn compact_currency(value: f64, step: f64, max: f64, symbol: &str) -> String {
+    let (scale, suffix) = match max.abs() {
+        m if m >= 1e9 => (1e9, "B"),
+        m if m >= 1e6 => (1e6, "M"),
+        m if m >= 1e4 => (1e3, "k"),
+        _ => (1.0, "")
+    };
+
+    let decimals = if step > 0.0 {
+        (-(step / scale).log10()).ceil().clamp(0.0, 4.0) as usize
+    } else {
+        0
+    };
+
+    let amount = thousands(value / scale, decimals);
+    match amount.strip_prefix('-') {
+        Some(amount) => format!("-{}{}{}", symbol, amount, suffix),
+        None => format!("{}{}{}", symbol, amount, suffix)
+    }
+}
+
+/// Rough length of a period of seconds in its two largest units, e.g. `3d 4h`
+pub fn duration(seconds: f64) -> String {
+    let seconds = seconds.max(0.0) as u64;
+    let (days, hours, minutes) = (seconds / 86400, seconds % 86400 / 3600, seconds % 3600 / 60);
+
+    match (days, hours, minutes) {
+        (0, 0, m) => format!("{}m", m),
+        (0, h, m) => format!("{}h {}m", h, m),
+        (d, h, _) => format!("{}d {}h", d, h)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thousands_groups_and_signs() {
+        assert_eq!(thousands(0.0, 0), "0");
+        assert_eq!(thousands(999.0, 0), "999");
+        assert_eq!(thousands(1000.0, 0), "1,000");
+        assert_eq!(thousands(1234567.891, 2), "1,234,567.89");
+        assert_eq!(thousands(-1000.0, 1), "-1,000.0");
+        assert_eq!(thousands(-123456.0, 0), "-123,456");
+        // Values that round to zero don't keep their sign
+        assert_eq!(thousands(-0.001, 2), "0.00");
+    }
+
+    #[test]
+    fn compact_currency_scales_and_decimals() {
+        assert_eq!(compact_currency(1_500_000.0, 250_000.0, 2e6, "$"), "$1.5M");
+        assert_eq!(compact_currency(3e9, 1e9, 3e9, "$"), "$3B");
+        assert_eq!(compact_currency(-25_000.0, 5_000.0, 50_000.0, "€"), "-€25k");
+        // Below 10k amounts aren't scaled
+        assert_eq!(compact_currency(9_999.0, 1.0, 9_999.0, "$"), "$9,999");
+    }
+
+    #[test]
+    fn compact_currency_step() {
+        // Without a positive step there is nothing to tell apart
+        assert_eq!(compact_currency(1234.0, 0.0, 1234.0, "$"), "$1,234");
+        assert_eq!(compact_currency(1234.0, -5.0, 1234.0, "$"), "$1,234");
+        // Sub-unit steps add decimals up to four
+        assert_eq!(compact_currency(1.2346, 0.001, 2.0, "$"), "$1.235");
+        assert_eq!(compact_currency(0.5, 1e-7, 1.0, "$"), "$0.5000");
+        assert_eq!(compact_currency(-0.25, 0.01, 1.0, "$"), "-$0.25");
+    }
+
+    #[test]
+    fn duration_two_largest_units() {
+        assert_eq!(duration(0.0), "0m");
+        assert_eq!(duration(59.0), "0m");
+        assert_eq!(duration(-100.0), "0m");
+        assert_eq!(duration(3720.0), "1h 2m");
+        assert_eq!(duration(3.0 * 86400.0 + 4.0 * 3600.0 + 59.0), "3d 4h");
+    }
+}
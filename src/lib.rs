@@ -1,34 +1,51 @@
+//! Terminal UI for Lean backtests, loaded by Lean as a shared library. Lean binds these C
+//! symbols, passing back the handle `initialize` returns and UTF-8, NUL terminated strings:
+//!
+//! | Symbol           | Arguments                  | Purpose                                         |
+//! |------------------|----------------------------|-------------------------------------------------|
+//! | `initialize`     |                            | Starts the terminal and returns its handle      |
+//! | `update`         | handle, JSON               | Sends a serialized `BacktestResultPacket`       |
+//! | `trace`          | handle, message            | Adds a trace log line                           |
+//! | `debug`          | handle, message            | Adds a debug log line                           |
+//! | `log_message`    | handle, message            | Adds a log line, for Lean's `Log`               |
+//! | `error`          | handle, message            | Adds an error, for Lean's `Error`               |
+//! | `export_on_free` | handle, bool               | Whether CSV exports are written on `free`       |
+//! | `free`           | handle                     | Writes exports and the report, restores the tty |
+//!
+//! The `Log` entry point used to be exported as `log`. Bindings still looking up `log` have to
+//! switch to `log_message`, the old name resolved to libm's `log` instead.
+
+pub mod analytics;
+pub mod config;
+pub mod export;
+pub mod format;
 pub mod model;
+pub mod portfolio;
+pub mod report;
 pub mod terminal;
 
-use std::io::{stdout, Stdout, Write};
-use std::thread;
-use std::mem::ManuallyDrop;
+#[cfg(test)]
+mod test_util;
+
+use std::io::Write;
 use std::{
     os::raw::c_char, 
-    sync::{Arc, Mutex}, 
+    sync::atomic::Ordering,
     ffi::CStr
 };
 
 use crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture},
+    event::DisableMouseCapture,
     execute, 
-    terminal::{EnterAlternateScreen, LeaveAlternateScreen, enable_raw_mode, disable_raw_mode}, 
-};
-use tui::Terminal;
-use tui::backend::CrosstermBackend;
-use tui::widgets::{Block, Borders, List, ListItem, Chart, Dataset, GraphType, Axis};
-use tui::{
-    text::Span, 
-    layout::{Layout, Constraint, Direction}, 
-    style::{Style, Color}, symbols::Marker
+    terminal::{LeaveAlternateScreen, disable_raw_mode}, 
 };
 
-use crate::terminal::terminal_handler::TerminalHandler;
+use crate::terminal::{logs::LogLevel, terminal_handler::TerminalHandler};
 
 pub enum Message {
-    Packet(model::BacktestResultPacket),
-    Log(String, bool),
+    Packet(Box<model::BacktestResultPacket>),
+    Log(String, LogLevel),
+    Input(crossterm::event::Event),
     Stop
 }
 
@@ -46,10 +63,10 @@ unsafe extern "C" fn update(handler: *mut TerminalHandler, raw_msg: *const c_cha
     let message = std::str::from_utf8(raw_msg.to_bytes()).unwrap();
 
     let terminal = Box::from_raw(handler);
-    let de_packet = serde_json::from_str::<model::BacktestResultPacket>(&message);
+    let de_packet = serde_json::from_str::<model::BacktestResultPacket>(message);
     
     match de_packet {
-        Ok(packet) => terminal.tx.send(Message::Packet(packet)).unwrap(),
+        Ok(packet) => terminal.tx.send(Message::Packet(Box::new(packet))).unwrap(),
         Err(err) => {
             std::fs::write("bterror.log", format!("{:?}", err)).unwrap();
             std::fs::write("btresultpacket.json", message).unwrap();
@@ -61,27 +78,35 @@ unsafe extern "C" fn update(handler: *mut TerminalHandler, raw_msg: *const c_cha
 
 #[no_mangle]
 unsafe extern "C" fn trace(handler: *mut TerminalHandler, raw_msg: *const c_char) {
-    let raw_msg = CStr::from_ptr(raw_msg);
-    let message = std::str::from_utf8(raw_msg.to_bytes()).unwrap();
-
-    let terminal = Box::from_raw(handler);
-    let log_msg = Message::Log(message.into(), false);
-
-    match terminal.tx.send(log_msg) {
-        Ok(_) => {},
-        Err(e) => println!("{:?}", e)
-    }
+    send_log(handler, raw_msg, LogLevel::Trace);
+}
 
-    std::mem::forget(terminal);
+#[no_mangle]
+unsafe extern "C" fn debug(handler: *mut TerminalHandler, raw_msg: *const c_char) {
+    send_log(handler, raw_msg, LogLevel::Debug);
 }
 
+/// Messages from Lean's `Log`. Not exported as `log`, since that would also take over libm's
+/// `log` for every `f64::ln` in this library.
 #[no_mangle]
+unsafe extern "C" fn log_message(handler: *mut TerminalHandler, raw_msg: *const c_char) {
+    send_log(handler, raw_msg, LogLevel::Log);
+}
+
+// `error` and `free` collide with libc symbols, which would hijack the allocator of the test
+// binary
+#[cfg_attr(not(test), no_mangle)]
+#[cfg_attr(test, allow(dead_code))]
 unsafe extern "C" fn error(handler: *mut TerminalHandler, raw_msg: *const c_char) {
+    send_log(handler, raw_msg, LogLevel::Error);
+}
+
+unsafe fn send_log(handler: *mut TerminalHandler, raw_msg: *const c_char, level: LogLevel) {
     let raw_msg = CStr::from_ptr(raw_msg);
     let message = std::str::from_utf8(raw_msg.to_bytes()).unwrap();
 
     let terminal = Box::from_raw(handler);
-    let log_msg = Message::Log(message.into(), true);
+    let log_msg = Message::Log(message.into(), level);
 
     match terminal.tx.send(log_msg) {
         Ok(_) => {},
@@ -91,13 +116,35 @@ unsafe extern "C" fn error(handler: *mut TerminalHandler, raw_msg: *const c_char
     std::mem::forget(terminal);
 }
 
+/// Writes orders and chart series as CSV into the configured export directory when the
+/// terminal is freed
 #[no_mangle]
-unsafe extern "C" fn free(handler: *mut TerminalHandler) {
+unsafe extern "C" fn export_on_free(handler: *mut TerminalHandler, enabled: bool) {
     let terminal = Box::from_raw(handler);
+    terminal.export_on_free.store(enabled, Ordering::Relaxed);
+    std::mem::forget(terminal);
+}
+
+#[cfg_attr(not(test), no_mangle)]
+#[cfg_attr(test, allow(dead_code))]
+unsafe extern "C" fn free(handler: *mut TerminalHandler) {
+    let mut terminal = Box::from_raw(handler);
 
     disable_raw_mode().unwrap();
+    terminal.running.store(false, Ordering::Relaxed);
+
+    // Stdin goes back to Lean, so the input thread has to stop reading it first
+    if let Some(input_thread) = terminal.input_thread.take() {
+        let _ = input_thread.join();
+    }
+
     terminal.tx.send(Message::Stop).unwrap();
 
-    execute!((*terminal).terminal.lock().unwrap().backend_mut(), LeaveAlternateScreen, DisableMouseCapture)
+    // Anything written on stop, like exports, has to finish before Lean exits
+    if let Some(bg_thread) = terminal.bg_thread.take() {
+        let _ = bg_thread.join();
+    }
+
+    execute!(terminal.terminal.lock().unwrap().backend_mut(), LeaveAlternateScreen, DisableMouseCapture)
         .unwrap();
 }
\ No newline at end of file
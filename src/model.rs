@@ -1,6 +1,11 @@
+// Field names mirror Lean's C# packet definitions so they line up with the serialized JSON
+#![allow(non_snake_case)]
+
 use serde::*;
 use std::fmt::Display;
-use tui::{style::{Style, Color, Modifier}, text::Span};
+use tui::{style::{Style, Modifier}, text::Span};
+
+use crate::terminal::theme::Theme;
 
 #[derive(Serialize, Deserialize)]
 pub enum PacketType
@@ -204,7 +209,7 @@ pub struct BacktestResultPacket {
     pub TradeableDates: i32
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Empty {}
 
 #[derive(Serialize, Deserialize)]
@@ -215,10 +220,30 @@ pub struct BacktestResults {
     pub Charts: Option<std::collections::HashMap<String, Chart>>,
 
     pub Orders: Option<std::collections::HashMap<String, Order>>,
+
+    pub Statistics: Option<std::collections::HashMap<String, String>>,
+
+    pub RuntimeStatistics: Option<std::collections::HashMap<String, String>>,
+
+    pub AlgorithmConfiguration: Option<AlgorithmConfiguration>,
+
+    pub OrderEvents: Option<Vec<OrderEvent>>,
     //pub ProfitLoss: std::collections::HashMap<String, f64>
 }
 
+/// <summary>
+/// This class includes algorithm configuration settings and parameters.
+/// This is used to include configuration parameters in the result packet to be used for report generation.
+/// </summary>
 #[derive(Serialize, Deserialize)]
+pub struct AlgorithmConfiguration {
+    /// <summary>
+    /// The algorithm's account currency
+    /// </summary>
+    pub AccountCurrency: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Order {
     pub Id: u64,
     pub ContingentId: i64,
@@ -235,20 +260,20 @@ pub struct Order {
     pub Type: i32,
     pub Status: i32,
 
-    #[serde(skip)]
-    pub TimeInForce: Option<Empty>,
+    #[serde(default)]
+    pub TimeInForce: Option<serde_json::Value>,
 
     pub Tag: Option<String>,
 
-    #[serde(skip)]
-    pub Properties: Option<Empty>,
+    #[serde(default)]
+    pub Properties: Option<serde_json::Value>,
 
     pub SecurityType: i32,
     pub Direction: i32,
     pub Value: f64,
 
-    #[serde(skip)]
-    pub OrderSubmissionData: Option<Empty>,
+    #[serde(default)]
+    pub OrderSubmissionData: Option<serde_json::Value>,
 
     pub IsMarketable: bool,
 
@@ -259,7 +284,38 @@ pub struct Order {
     pub StopTriggered: Option<bool>,
 }
 
-#[derive(Serialize, Deserialize)]
+/// <summary>
+/// Order event details containing fill information. Lean serializes these in camelCase,
+/// older versions used the C# property names.
+/// </summary>
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OrderEvent {
+    #[serde(rename = "orderId", alias = "OrderId")]
+    pub OrderId: u64,
+
+    /// Counts up from 0 per order, so it's only unique together with `OrderId`
+    #[serde(rename = "orderEventId", alias = "Id")]
+    pub EventId: u64,
+
+    #[serde(rename = "symbolValue", alias = "SymbolValue", default)]
+    pub SymbolValue: Option<String>,
+
+    /// Unix time in seconds
+    #[serde(rename = "time", default)]
+    pub Time: Option<f64>,
+
+    #[serde(rename = "fillPrice", alias = "FillPrice", default)]
+    pub FillPrice: f64,
+
+    /// Signed, negative for sells
+    #[serde(rename = "fillQuantity", alias = "FillQuantity", default)]
+    pub FillQuantity: f64,
+
+    #[serde(rename = "orderFeeAmount", alias = "OrderFeeAmount", default)]
+    pub OrderFeeAmount: Option<f64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Symbol {
     pub Value: String,
     pub ID: String,
@@ -379,20 +435,99 @@ pub enum OrderType {
     OptionExercise = 6
 }
 
+pub enum OrderStatus {
+    /// <summary>
+    /// New order pre-submission to the order processor.
+    /// </summary>
+    New = 0,
+
+    /// <summary>
+    /// Order submitted to the market
+    /// </summary>
+    Submitted = 1,
+
+    /// <summary>
+    /// Partially filled, In Market Order.
+    /// </summary>
+    PartiallyFilled = 2,
+
+    /// <summary>
+    /// Completed, Filled, In Market Order.
+    /// </summary>
+    Filled = 3,
+
+    /// <summary>
+    /// Order cancelled before it was filled
+    /// </summary>
+    Canceled = 5,
+
+    /// <summary>
+    /// No Order State Yet
+    /// </summary>
+    None = 6,
+
+    /// <summary>
+    /// Order invalidated before it hit the market (e.g. insufficient capital)..
+    /// </summary>
+    Invalid = 7,
+
+    /// <summary>
+    /// Order waiting for confirmation of cancellation
+    /// </summary>
+    CancelPending = 8,
+
+    /// <summary>
+    /// Order update submitted to the market
+    /// </summary>
+    UpdateSubmitted = 9
+}
+
 impl Order {
-    pub fn into_spans<'a>(&self) -> (Span<'a>, Span<'a>, Span<'a>, Span<'a>, Span<'a>) {
+    pub fn into_spans<'a>(&self, theme: &Theme) -> (Span<'a>, Span<'a>, Span<'a>, Span<'a>, Span<'a>) {
         (
             Span::raw(self.Time.clone()),
             Span::styled(OrderType::from(self.Type).to_string(), Style::default().add_modifier(Modifier::BOLD)),
-            direction_to_span(self.Direction),
+            direction_to_span(self.Direction, theme),
             Span::raw(self.Quantity.to_string()),
             Span::raw(self.Symbol.Value.clone())
         )
     }
 }
 
+impl Display for OrderStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderStatus::New => write!(f, "New"),
+            OrderStatus::Submitted => write!(f, "Submitted"),
+            OrderStatus::PartiallyFilled => write!(f, "PartFilled"),
+            OrderStatus::Filled => write!(f, "Filled"),
+            OrderStatus::Canceled => write!(f, "Canceled"),
+            OrderStatus::None => write!(f, "None"),
+            OrderStatus::Invalid => write!(f, "Invalid"),
+            OrderStatus::CancelPending => write!(f, "CancelPending"),
+            OrderStatus::UpdateSubmitted => write!(f, "UpdateSubmitted"),
+        }
+    }
+}
+
+impl From<i32> for OrderStatus {
+    fn from(t: i32) -> Self {
+        match t {
+            0 => OrderStatus::New,
+            1 => OrderStatus::Submitted,
+            2 => OrderStatus::PartiallyFilled,
+            3 => OrderStatus::Filled,
+            5 => OrderStatus::Canceled,
+            7 => OrderStatus::Invalid,
+            8 => OrderStatus::CancelPending,
+            9 => OrderStatus::UpdateSubmitted,
+            _ => OrderStatus::None
+        }
+    }
+}
+
 impl Display for OrderType {
-    fn fmt(&self, f: &mut export::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             OrderType::Limit => write!(f, "LMT"),
             OrderType::Market => write!(f, "MKT"),
@@ -405,21 +540,27 @@ impl Display for OrderType {
     }
 }
 
-fn direction_to_span<'a>(direction: i32) -> Span<'a> {
-    let (color, display) = if direction == 0 { 
-        (Color::Green,
-        "BOT".to_string())
+pub fn direction_to_span<'a>(direction: i32, theme: &Theme) -> Span<'a> {
+    let color = if direction == 0 { 
+        theme.buy
     } 
     else if direction == 1 { 
-        (Color::Red,
-        "SLD".to_string())
+        theme.sell
     } 
     else { 
-        (Color::White,
-        "HLD".to_string())
+        theme.hold
     };
 
-    Span::styled(display, Style::default().add_modifier(Modifier::BOLD).fg(color))
+    Span::styled(direction_name(direction), Style::default().add_modifier(Modifier::BOLD).fg(color))
+}
+
+/// Short name of an order direction, as shown in the order tables
+pub fn direction_name(direction: i32) -> &'static str {
+    match direction {
+        0 => "BOT",
+        1 => "SLD",
+        _ => "HLD"
+    }
 }
 
 impl From<i32> for OrderType {
@@ -0,0 +1,589 @@
+//! Positions rebuilt from the order history, since the result packet only carries orders
+//! and the fills that completed them.
+
+use serde::Deserialize;
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::model::{Order, OrderEvent, OrderStatus};
+
+/// Quantities closer to zero than this are flat. Lean uses decimals, so fractional fills
+/// summed as floats don't always cancel exactly.
+const FLAT: f64 = 1e-9;
+
+/// A single execution, taken from an order event or from a filled order
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Fill {
+    pub order_id: u64,
+    pub symbol: String,
+    /// Unix time in seconds, 0 if unknown
+    pub time: f64,
+    pub price: f64,
+    /// Signed, negative for sells
+    pub quantity: f64,
+    pub fee: f64,
+}
+
+/// Executions in the order they happened. Order events carry every partial fill, so they're
+/// used when Lean sends them. Older versions only send orders, where a filled order counts as
+/// one fill at its average price and partially filled orders are skipped since the filled
+/// quantity isn't known.
+pub fn fills(orders: &[Order], events: &[OrderEvent]) -> Vec<Fill> {
+    let mut fills = if events.is_empty() {
+        orders
+            .iter()
+            .filter(|o| matches!(OrderStatus::from(o.Status), OrderStatus::Filled) && o.Quantity != 0.0)
+            .map(|o| Fill {
+                order_id: o.Id,
+                symbol: o.Symbol.Value.clone(),
+                time: o.LastFillTime.as_deref().map(parse_time).unwrap_or(0.0),
+                price: o.Price,
+                quantity: o.Quantity,
+                fee: 0.0
+            })
+            .collect::<Vec<Fill>>()
+    } else {
+        events
+            .iter()
+            .filter(|e| e.FillQuantity != 0.0)
+            .map(|e| Fill {
+                order_id: e.OrderId,
+                symbol: e.SymbolValue.clone().or_else(|| symbol_of(orders, e.OrderId)).unwrap_or_default(),
+                time: e.Time.unwrap_or(0.0),
+                price: e.FillPrice,
+                quantity: e.FillQuantity,
+                fee: e.OrderFeeAmount.unwrap_or(0.0)
+            })
+            .collect::<Vec<Fill>>()
+    };
+
+    fills.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap().then(a.order_id.cmp(&b.order_id)));
+    fills
+}
+
+fn symbol_of(orders: &[Order], id: u64) -> Option<String> {
+    orders.iter().find(|o| o.Id == id).map(|o| o.Symbol.Value.clone())
+}
+
+/// Unix time of one of Lean's ISO 8601 timestamps, 0 if it can't be parsed
+pub fn parse_time(time: &str) -> f64 {
+    chrono::DateTime::parse_from_rfc3339(time)
+        .map(|t| t.timestamp() as f64)
+        .or_else(|_| chrono::NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M:%S%.f").map(|t| t.and_utc().timestamp() as f64))
+        .unwrap_or(0.0)
+}
+
+/// Holdings of one symbol after replaying its fills
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Position {
+    pub symbol: String,
+    /// Signed, negative when short
+    pub quantity: f64,
+    /// Average price the open quantity was entered at
+    pub average_price: f64,
+    /// Profit taken by reducing or closing the position, before fees
+    pub realized: f64,
+    pub fees: f64,
+    /// Price of the latest fill
+    pub last_fill_price: Option<f64>,
+    /// Latest price of the symbol from the "Assets" chart, which open quantity is valued at
+    pub mark_price: Option<f64>,
+}
+
+impl Position {
+    /// Applies a fill. Fills in the direction of the position average into its cost, fills
+    /// against it realize profit on the closed quantity, and a fill larger than the position
+    /// flips it at the fill price.
+    pub fn apply(&mut self, fill: &Fill) {
+        let opening = self.quantity == 0.0 || self.quantity.signum() == fill.quantity.signum();
+
+        if opening {
+            let total = self.quantity.abs() + fill.quantity.abs();
+            self.average_price = (self.average_price * self.quantity.abs() + fill.price * fill.quantity.abs()) / total;
+            self.quantity += fill.quantity;
+        } else {
+            let closed = fill.quantity.abs().min(self.quantity.abs());
+            self.realized += closed * (fill.price - self.average_price) * self.quantity.signum();
+
+            let mut remaining = self.quantity + fill.quantity;
+            if remaining.abs() < FLAT {
+                remaining = 0.0;
+                self.average_price = 0.0;
+            } else if remaining.signum() != self.quantity.signum() {
+                self.average_price = fill.price;
+            }
+            self.quantity = remaining;
+        }
+
+        self.fees += fill.fee;
+        self.last_fill_price = Some(fill.price);
+    }
+
+    /// Profit of the open quantity marked at `mark_price`, `None` without a mark
+    pub fn unrealized(&self) -> Option<f64> {
+        self.mark_price.map(|price| (price - self.average_price) * self.quantity)
+    }
+
+    pub fn market_value(&self) -> Option<f64> {
+        self.mark_price.map(|price| price * self.quantity)
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.quantity != 0.0
+    }
+}
+
+/// Positions of every symbol that was traded, sorted by symbol. Each is marked at the latest
+/// point of its price series in `prices` when there is one, and left unmarked otherwise.
+pub fn positions(fills: &[Fill], prices: &BTreeMap<String, Vec<(f64, f64)>>) -> Vec<Position> {
+    let mut positions: BTreeMap<&str, Position> = BTreeMap::new();
+    for fill in fills {
+        positions
+            .entry(fill.symbol.as_str())
+            .or_insert_with(|| Position { symbol: fill.symbol.clone(), ..Default::default() })
+            .apply(fill);
+    }
+
+    for position in positions.values_mut() {
+        if let Some(&(_, price)) = prices.get(&position.symbol).and_then(|series| series.last()) {
+            position.mark_price = Some(price);
+        }
+    }
+
+    positions.into_values().collect()
+}
+
+/// Which open lot an exit is matched against, as written in the config file
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Matching {
+    /// Oldest lot first
+    #[default]
+    Fifo,
+    /// Newest lot first
+    Lifo
+}
+
+impl Matching {
+    pub fn next(&self) -> Self {
+        match self {
+            Matching::Fifo => Matching::Lifo,
+            Matching::Lifo => Matching::Fifo
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Matching::Fifo => "FIFO",
+            Matching::Lifo => "LIFO"
+        }
+    }
+}
+
+/// A closed round trip: an entry fill, or the part of it an exit closed
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Trade {
+    pub symbol: String,
+    pub entry_time: f64,
+    pub exit_time: f64,
+    pub entry_price: f64,
+    pub exit_price: f64,
+    /// Signed, negative for shorts
+    pub quantity: f64,
+    /// Before fees
+    pub pnl: f64,
+}
+
+impl Trade {
+    /// Percent return on the entry value
+    pub fn return_pct(&self) -> f64 {
+        let cost = (self.entry_price * self.quantity).abs();
+        if cost > 0.0 { self.pnl / cost * 100.0 } else { 0.0 }
+    }
+
+    /// Holding period in seconds
+    pub fn duration(&self) -> f64 {
+        (self.exit_time - self.entry_time).max(0.0)
+    }
+}
+
+/// Pairs exits with the open entries of the same symbol, splitting fills where quantities
+/// don't line up. Returns the closed trades in the order they were closed.
+pub fn round_trips(fills: &[Fill], matching: Matching) -> Vec<Trade> {
+    let mut lots: BTreeMap<&str, VecDeque<Fill>> = BTreeMap::new();
+    let mut trades = Vec::new();
+
+    for fill in fills {
+        let open = lots.entry(fill.symbol.as_str()).or_default();
+        let mut remaining = fill.quantity;
+
+        while remaining.abs() >= FLAT {
+            let lot = match matching {
+                Matching::Fifo => open.front_mut(),
+                Matching::Lifo => open.back_mut()
+            };
+            let lot = match lot {
+                Some(lot) if lot.quantity.signum() != remaining.signum() => lot,
+                _ => break
+            };
+
+            let closed = remaining.abs().min(lot.quantity.abs()) * lot.quantity.signum();
+            trades.push(Trade {
+                symbol: fill.symbol.clone(),
+                entry_time: lot.time,
+                exit_time: fill.time,
+                entry_price: lot.price,
+                exit_price: fill.price,
+                quantity: closed,
+                pnl: closed * (fill.price - lot.price)
+            });
+
+            lot.quantity -= closed;
+            remaining += closed;
+            if lot.quantity.abs() < FLAT {
+                match matching {
+                    Matching::Fifo => open.pop_front(),
+                    Matching::Lifo => open.pop_back()
+                };
+            }
+        }
+
+        if remaining.abs() >= FLAT {
+            open.push_back(Fill { quantity: remaining, ..fill.clone() });
+        }
+    }
+
+    trades
+}
+
+/// Summary of a set of closed trades
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TradeStats {
+    pub count: usize,
+    /// Percent of trades with a positive P&L
+    pub win_rate: f64,
+    /// Gross profit over gross loss, `None` without losing trades
+    pub profit_factor: Option<f64>,
+    pub average_win: f64,
+    /// Negative
+    pub average_loss: f64,
+    /// Average P&L per trade
+    pub expectancy: f64,
+    pub longest_win_streak: usize,
+    pub longest_loss_streak: usize,
+}
+
+impl TradeStats {
+    pub fn new<'a>(trades: impl Iterator<Item = &'a Trade>) -> Option<Self> {
+        let mut stats = Self::default();
+        let (mut wins, mut losses) = (0usize, 0usize);
+        let (mut profit, mut loss) = (0.0, 0.0);
+        let (mut win_streak, mut loss_streak) = (0usize, 0usize);
+
+        for trade in trades {
+            stats.count += 1;
+            if trade.pnl > 0.0 {
+                wins += 1;
+                profit += trade.pnl;
+                win_streak += 1;
+                loss_streak = 0;
+            } else if trade.pnl < 0.0 {
+                losses += 1;
+                loss += trade.pnl;
+                loss_streak += 1;
+                win_streak = 0;
+            } else {
+                win_streak = 0;
+                loss_streak = 0;
+            }
+
+            stats.longest_win_streak = stats.longest_win_streak.max(win_streak);
+            stats.longest_loss_streak = stats.longest_loss_streak.max(loss_streak);
+        }
+
+        if stats.count == 0 {
+            return None
+        }
+
+        stats.win_rate = wins as f64 / stats.count as f64 * 100.0;
+        stats.profit_factor = if loss < 0.0 { Some(profit / -loss) } else { None };
+        stats.average_win = if wins > 0 { profit / wins as f64 } else { 0.0 };
+        stats.average_loss = if losses > 0 { loss / losses as f64 } else { 0.0 };
+        stats.expectancy = (profit + loss) / stats.count as f64;
+        Some(stats)
+    }
+}
+
+/// Contribution of one symbol to the book
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Attribution {
+    pub symbol: String,
+    pub realized: f64,
+    /// Open quantity marked at the latest price, 0 without one, see `Position::mark_price`
+    pub unrealized: f64,
+    /// Orders of any status
+    pub orders: usize,
+    /// Absolute value of every fill
+    pub volume: f64,
+    pub fees: f64,
+}
+
+impl Attribution {
+    /// Realized and unrealized P&L, after fees
+    pub fn total(&self) -> f64 {
+        self.realized + self.unrealized - self.fees
+    }
+}
+
+/// P&L, activity and costs of every symbol that was ordered, sorted by symbol. `positions`
+/// are the ones rebuilt from `fills`.
+pub fn attribution(orders: &[Order], fills: &[Fill], positions: &[Position]) -> Vec<Attribution> {
+    let mut symbols: BTreeMap<String, Attribution> = BTreeMap::new();
+    for order in orders {
+        attribution_of(&mut symbols, &order.Symbol.Value).orders += 1;
+    }
+    for fill in fills {
+        attribution_of(&mut symbols, &fill.symbol).volume += (fill.quantity * fill.price).abs();
+    }
+    for position in positions {
+        let symbol = attribution_of(&mut symbols, &position.symbol);
+        symbol.realized = position.realized;
+        symbol.unrealized = position.unrealized().unwrap_or(0.0);
+        symbol.fees = position.fees;
+    }
+
+    symbols.into_values().collect()
+}
+
+fn attribution_of<'a>(symbols: &'a mut BTreeMap<String, Attribution>, symbol: &str) -> &'a mut Attribution {
+    symbols
+        .entry(symbol.to_string())
+        .or_insert_with(|| Attribution { symbol: symbol.to_string(), ..Default::default() })
+}
+
+/// Column the attribution table is sorted by
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum AttributionColumn {
+    Symbol,
+    Realized,
+    Unrealized,
+    #[default]
+    Total,
+    Orders,
+    Volume,
+    Fees
+}
+
+impl AttributionColumn {
+    /// Columns in the order they're shown
+    pub const ALL: [AttributionColumn; 7] = [
+        AttributionColumn::Symbol,
+        AttributionColumn::Realized,
+        AttributionColumn::Unrealized,
+        AttributionColumn::Total,
+        AttributionColumn::Orders,
+        AttributionColumn::Volume,
+        AttributionColumn::Fees
+    ];
+
+    pub fn next(&self) -> Self {
+        let i = Self::ALL.iter().position(|c| c == self).unwrap();
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AttributionColumn::Symbol => "Symbol",
+            AttributionColumn::Realized => "Realized",
+            AttributionColumn::Unrealized => "Unrealized",
+            AttributionColumn::Total => "Total",
+            AttributionColumn::Orders => "Orders",
+            AttributionColumn::Volume => "Volume",
+            AttributionColumn::Fees => "Fees"
+        }
+    }
+
+    /// Sorts `rows` by this column, ascending unless `descending`
+    pub fn sort(&self, rows: &mut [Attribution], descending: bool) {
+        rows.sort_by(|a, b| {
+            let ordering = match self {
+                AttributionColumn::Symbol => a.symbol.cmp(&b.symbol),
+                AttributionColumn::Realized => a.realized.partial_cmp(&b.realized).unwrap(),
+                AttributionColumn::Unrealized => a.unrealized.partial_cmp(&b.unrealized).unwrap(),
+                AttributionColumn::Total => a.total().partial_cmp(&b.total()).unwrap(),
+                AttributionColumn::Orders => a.orders.cmp(&b.orders),
+                AttributionColumn::Volume => a.volume.partial_cmp(&b.volume).unwrap(),
+                AttributionColumn::Fees => a.fees.partial_cmp(&b.fees).unwrap()
+            };
+
+            if descending { ordering.reverse() } else { ordering }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::assert_close;
+
+    fn fill(time: f64, quantity: f64, price: f64) -> Fill {
+        Fill { symbol: "SPY".to_string(), time, quantity, price, fee: 1.0, ..Default::default() }
+    }
+
+    fn trade(pnl: f64) -> Trade {
+        Trade { pnl, ..Default::default() }
+    }
+
+    #[test]
+    fn position_partial_fills() {
+        let mut position = Position::default();
+        position.apply(&fill(1.0, 10.0, 100.0));
+        position.apply(&fill(2.0, 10.0, 110.0));
+        assert_close(position.quantity, 20.0);
+        assert_close(position.average_price, 105.0);
+
+        position.apply(&fill(3.0, -5.0, 120.0));
+        assert_close(position.quantity, 15.0);
+        assert_close(position.average_price, 105.0);
+        assert_close(position.realized, 75.0);
+        assert_close(position.fees, 3.0);
+        assert_eq!(position.last_fill_price, Some(120.0));
+        assert_eq!(position.unrealized(), None);
+
+        position.mark_price = Some(120.0);
+        assert_close(position.unrealized().unwrap(), 225.0);
+    }
+
+    #[test]
+    fn position_short() {
+        let mut position = Position::default();
+        position.apply(&fill(1.0, -10.0, 50.0));
+        position.apply(&fill(2.0, 4.0, 40.0));
+
+        assert_close(position.quantity, -6.0);
+        assert_close(position.average_price, 50.0);
+        assert_close(position.realized, 40.0);
+
+        position.mark_price = Some(40.0);
+        assert_close(position.unrealized().unwrap(), 60.0);
+    }
+
+    #[test]
+    fn position_flips_through_zero() {
+        let mut position = Position::default();
+        position.apply(&fill(1.0, 10.0, 100.0));
+        position.apply(&fill(2.0, -15.0, 110.0));
+        assert_close(position.quantity, -5.0);
+        assert_close(position.average_price, 110.0);
+        assert_close(position.realized, 100.0);
+
+        position.apply(&fill(3.0, 5.0, 105.0));
+        assert!(!position.is_open());
+        assert_close(position.average_price, 0.0);
+        assert_close(position.realized, 125.0);
+    }
+
+    #[test]
+    fn position_fractional_fills_close_flat() {
+        let fills = [fill(1.0, 3.0, 10.0), fill(2.0, 7.0, 20.0), fill(3.0, -0.1, 20.0), fill(4.0, -0.2, 20.0), fill(5.0, -9.7, 20.0)];
+        let positions = positions(&fills, &BTreeMap::new());
+
+        assert_eq!(positions.len(), 1);
+        assert!(!positions[0].is_open());
+        assert_close(positions[0].realized, 30.0);
+    }
+
+    #[test]
+    fn round_trips_fifo_splits_lots() {
+        let fills = [fill(1.0, 10.0, 100.0), fill(2.0, 10.0, 110.0), fill(3.0, -15.0, 120.0)];
+        let trades = round_trips(&fills, Matching::Fifo);
+
+        assert_eq!(trades.len(), 2);
+        assert_eq!((trades[0].entry_time, trades[0].entry_price, trades[0].exit_time), (1.0, 100.0, 3.0));
+        assert_close(trades[0].quantity, 10.0);
+        assert_close(trades[0].pnl, 200.0);
+        assert_eq!(trades[1].entry_price, 110.0);
+        assert_close(trades[1].quantity, 5.0);
+        assert_close(trades[1].pnl, 50.0);
+
+        // The rest of the second lot closes on the next exit
+        let fills = [fills[0].clone(), fills[1].clone(), fills[2].clone(), fill(4.0, -5.0, 100.0)];
+        let last = round_trips(&fills, Matching::Fifo).pop().unwrap();
+        assert_eq!((last.entry_price, last.exit_time), (110.0, 4.0));
+        assert_close(last.pnl, -50.0);
+    }
+
+    #[test]
+    fn round_trips_lifo_splits_lots() {
+        let fills = [fill(1.0, 10.0, 100.0), fill(2.0, 10.0, 110.0), fill(3.0, -15.0, 120.0)];
+        let trades = round_trips(&fills, Matching::Lifo);
+
+        assert_eq!(trades.len(), 2);
+        assert_eq!(trades[0].entry_price, 110.0);
+        assert_close(trades[0].quantity, 10.0);
+        assert_close(trades[0].pnl, 100.0);
+        assert_eq!(trades[1].entry_price, 100.0);
+        assert_close(trades[1].quantity, 5.0);
+        assert_close(trades[1].pnl, 100.0);
+    }
+
+    #[test]
+    fn round_trips_short_and_flip() {
+        // The buy closes the short and opens a long with what's left
+        let fills = [fill(1.0, -5.0, 50.0), fill(2.0, 8.0, 40.0), fill(3.0, -3.0, 45.0)];
+        let trades = round_trips(&fills, Matching::Fifo);
+
+        assert_eq!(trades.len(), 2);
+        assert_close(trades[0].quantity, -5.0);
+        assert_close(trades[0].pnl, 50.0);
+        assert_close(trades[0].return_pct(), 20.0);
+        assert_close(trades[1].quantity, 3.0);
+        assert_eq!(trades[1].entry_price, 40.0);
+        assert_close(trades[1].pnl, 15.0);
+        assert_eq!(trades[1].duration(), 1.0);
+    }
+
+    #[test]
+    fn trade_stats_streaks_and_profit_factor() {
+        let trades = [10.0, 20.0, -5.0, 0.0, -5.0, -10.0, 30.0].iter().map(|&pnl| trade(pnl)).collect::<Vec<Trade>>();
+        let stats = TradeStats::new(trades.iter()).unwrap();
+
+        assert_eq!(stats.count, 7);
+        assert_eq!(stats.longest_win_streak, 2);
+        assert_eq!(stats.longest_loss_streak, 2);
+        assert_close(stats.profit_factor.unwrap(), 3.0);
+        assert_close(stats.win_rate, 3.0 / 7.0 * 100.0);
+        assert_close(stats.average_win, 20.0);
+        assert_close(stats.average_loss, -20.0 / 3.0);
+        assert_close(stats.expectancy, 40.0 / 7.0);
+    }
+
+    #[test]
+    fn trade_stats_without_losses() {
+        let trades = [trade(10.0), trade(5.0)];
+        let stats = TradeStats::new(trades.iter()).unwrap();
+
+        assert_eq!(stats.profit_factor, None);
+        assert_eq!(stats.longest_win_streak, 2);
+        assert_eq!(stats.longest_loss_streak, 0);
+        assert_eq!(TradeStats::new([].iter()), None);
+    }
+
+    #[test]
+    fn positions_marked_at_latest_price() {
+        let fills = [fill(1.0, 10.0, 100.0), fill(2.0, 10.0, 102.0)];
+        let mut prices = BTreeMap::new();
+
+        let position = &positions(&fills, &prices)[0];
+        assert_eq!(position.last_fill_price, Some(102.0));
+        assert_eq!(position.mark_price, None);
+        assert_eq!(position.unrealized(), None);
+        assert_eq!(position.market_value(), None);
+
+        prices.insert("SPY".to_string(), vec![(1.0, 100.0), (2.0, 104.0)]);
+        let position = &positions(&fills, &prices)[0];
+        assert_eq!(position.last_fill_price, Some(102.0));
+        assert_eq!(position.mark_price, Some(104.0));
+        assert_close(position.unrealized().unwrap(), 60.0);
+        assert_close(position.market_value().unwrap(), 2080.0);
+    }
+}
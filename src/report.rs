@@ -0,0 +1,556 @@
+use std::{fs, io, path::PathBuf};
+use serde::Deserialize;
+
+use crate::{analytics, model::{BacktestResultPacket, Order, OrderStatus, OrderType, direction_name}, terminal::chart::{ticks, value_labels}};
+
+/// Size of the Markdown report's ASCII charts, in characters
+const ASCII_WIDTH: usize = 72;
+const ASCII_HEIGHT: usize = 16;
+
+/// Size of the HTML report's SVG charts, in pixels
+const SVG_WIDTH: f64 = 900.0;
+const SVG_HEIGHT: f64 = 260.0;
+
+/// Number of value labels and gridlines on the SVG charts, including the top and bottom
+const SVG_LABELS: usize = 5;
+
+/// Room around the SVG plot for the value labels on the left and the dates below
+const SVG_LEFT: f64 = 90.0;
+const SVG_BOTTOM: f64 = 30.0;
+const SVG_TOP: f64 = 20.0;
+
+/// Line colors of the SVG charts, in the order series are passed
+const SVG_COLORS: [&str; 3] = ["#1f77b4", "#ff7f0e", "#d62728"];
+
+/// Characters marking the series of the ASCII charts, in the order series are passed
+const ASCII_MARKS: [char; 3] = ['*', '.', '#'];
+
+/// File format of the end-of-run report, `none` to not write one
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    None,
+    Markdown,
+    Html
+}
+
+impl ReportFormat {
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            ReportFormat::None => None,
+            ReportFormat::Markdown => Some("md"),
+            ReportFormat::Html => Some("html")
+        }
+    }
+}
+
+/// Details of the backtest from the latest result packet
+#[derive(Clone, Debug, Default)]
+pub struct Metadata {
+    pub name: String,
+    pub backtest_id: String,
+    pub project_id: i32,
+    pub period_start: String,
+    pub period_finish: String,
+    pub date_requested: String,
+    pub date_finished: String,
+    /// Share of the backtest period processed, from 0 to 1
+    pub progress: f64,
+    /// Seconds the algorithm ran for
+    pub processing_time: f64,
+    pub tradeable_dates: i32,
+}
+
+impl Metadata {
+    pub fn new(packet: &BacktestResultPacket) -> Self {
+        Self {
+            name: packet.Name.clone(),
+            backtest_id: packet.BacktestId.clone(),
+            project_id: packet.ProjectId,
+            period_start: packet.PeriodStart.clone(),
+            period_finish: packet.PeriodFinish.clone(),
+            date_requested: packet.DateRequested.clone(),
+            date_finished: packet.DateFinished.clone(),
+            progress: packet.Progress,
+            processing_time: packet.ProcessingTime,
+            tradeable_dates: packet.TradeableDates
+        }
+    }
+
+    fn rows(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("Backtest Id", self.backtest_id.clone()),
+            ("Project Id", self.project_id.to_string()),
+            ("Period", format!("{} to {}", self.period_start, self.period_finish)),
+            ("Requested", self.date_requested.clone()),
+            ("Finished", self.date_finished.clone()),
+            ("Progress", format!("{:.0}%", self.progress * 100.0)),
+            ("Processing Time", format!("{:.1}s", self.processing_time)),
+            ("Tradeable Dates", self.tradeable_dates.to_string()),
+        ]
+    }
+}
+
+/// Everything the report shows, borrowed from the terminal's data when the run stops
+pub struct Report<'a> {
+    pub metadata: &'a Metadata,
+    pub statistics: &'a [(String, String)],
+    pub runtime_statistics: &'a [(String, String)],
+    pub orders: &'a [Order],
+    pub equity: &'a [(f64, f64)],
+    pub benchmark: &'a [(f64, f64)],
+    /// Percent drawdown, as shown on the overview page
+    pub drawdown: &'a [(f64, f64)],
+    /// Symbol of the account currency, e.g. `$`
+    pub currency: &'a str,
+}
+
+impl<'a> Report<'a> {
+    /// Writes the report next to `path`, adding the format's extension. Returns the file
+    /// written, `None` if reports are turned off.
+    pub fn write(&self, format: ReportFormat, path: &str) -> io::Result<Option<PathBuf>> {
+        let contents = match format {
+            ReportFormat::None => return Ok(None),
+            ReportFormat::Markdown => self.markdown(),
+            ReportFormat::Html => self.html()
+        };
+
+        let path = PathBuf::from(format!("{}.{}", path, format.extension().unwrap()));
+        fs::write(&path, contents)?;
+        Ok(Some(path))
+    }
+
+    /// Self-contained Markdown report, with the charts drawn in ASCII
+    pub fn markdown(&self) -> String {
+        let mut md = format!("# {}\n\n", self.title());
+        md.push_str(&markdown_table(&["", ""], &self.metadata.rows().into_iter().map(|(k, v)| vec![k.to_string(), v]).collect::<Vec<_>>()));
+
+        md.push_str("\n## Equity\n\n```\n");
+        let benchmark = self.rebased_benchmark();
+        md.push_str(&ascii_chart(&[self.equity, &benchmark], |values| value_labels(values, self.currency)));
+        md.push_str(&format!("{} Equity  {} Benchmark\n```\n", ASCII_MARKS[0], ASCII_MARKS[1]));
+
+        md.push_str("\n## Drawdown\n\n```\n");
+        md.push_str(&ascii_chart(&[self.drawdown], percent_labels));
+        md.push_str("```\n");
+
+        md.push_str("\n## Statistics\n\n");
+        md.push_str(&markdown_table(&["Statistic", "Value"], &pairs(self.statistics)));
+
+        md.push_str("\n## Runtime Statistics\n\n");
+        md.push_str(&markdown_table(&["Statistic", "Value"], &pairs(self.runtime_statistics)));
+
+        md.push_str("\n## Monthly Returns %\n\n");
+        md.push_str(&markdown_table(&monthly_header(), &monthly_rows(self.equity)));
+
+        md.push_str(&format!("\n## Orders ({})\n\n", self.orders.len()));
+        md.push_str(&markdown_table(&ORDER_COLUMNS, &order_rows(self.orders)));
+
+        md
+    }
+
+    /// Self-contained HTML report, with the charts drawn as inline SVG
+    pub fn html(&self) -> String {
+        let title = escape_html(&self.title());
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n\
+            body {{ font-family: sans-serif; margin: 2em; }}\n\
+            table {{ border-collapse: collapse; margin-bottom: 1em; }}\n\
+            th, td {{ border: 1px solid #ddd; padding: 2px 8px; text-align: right; }}\n\
+            th {{ background: #f4f4f4; }}\n\
+            td:first-child, th:first-child {{ text-align: left; }}\n\
+            .negative {{ color: #c0392b; }}\n\
+            </style>\n</head>\n<body>\n<h1>{}</h1>\n",
+            title, title
+        );
+        html.push_str(&html_table(&[], &self.metadata.rows().into_iter().map(|(k, v)| vec![k.to_string(), v]).collect::<Vec<_>>()));
+
+        html.push_str("<h2>Equity</h2>\n");
+        let benchmark = self.rebased_benchmark();
+        html.push_str(&svg_chart(&[(self.equity, "Equity"), (&benchmark, "Benchmark")], |values| value_labels(values, self.currency)));
+
+        html.push_str("<h2>Drawdown</h2>\n");
+        html.push_str(&svg_chart(&[(self.drawdown, "Drawdown")], percent_labels));
+
+        html.push_str("<h2>Statistics</h2>\n");
+        html.push_str(&html_table(&["Statistic", "Value"], &pairs(self.statistics)));
+
+        html.push_str("<h2>Runtime Statistics</h2>\n");
+        html.push_str(&html_table(&["Statistic", "Value"], &pairs(self.runtime_statistics)));
+
+        html.push_str("<h2>Monthly Returns %</h2>\n");
+        html.push_str(&html_table(&monthly_header(), &monthly_rows(self.equity)));
+
+        html.push_str(&format!("<h2>Orders ({})</h2>\n", self.orders.len()));
+        html.push_str(&html_table(&ORDER_COLUMNS, &order_rows(self.orders)));
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    fn title(&self) -> String {
+        if self.metadata.name.is_empty() {
+            String::from("Backtest Report")
+        } else {
+            format!("{} Backtest Report", self.metadata.name)
+        }
+    }
+
+    /// Benchmark scaled to start at the first equity value, as on the equity chart
+    fn rebased_benchmark(&self) -> Vec<(f64, f64)> {
+        match self.equity.first() {
+            Some(&(x, y)) => analytics::rebase(self.benchmark, x, y),
+            None => vec![]
+        }
+    }
+}
+
+const ORDER_COLUMNS: [&str; 10] = ["Id", "Time", "Symbol", "Type", "Direction", "Quantity", "Price", "Status", "Value", "Tag"];
+
+fn order_rows(orders: &[Order]) -> Vec<Vec<String>> {
+    orders
+        .iter()
+        .map(|order| vec![
+            order.Id.to_string(),
+            order.Time.clone(),
+            order.Symbol.Value.clone(),
+            OrderType::from(order.Type).to_string(),
+            direction_name(order.Direction).to_string(),
+            order.Quantity.to_string(),
+            order.Price.to_string(),
+            OrderStatus::from(order.Status).to_string(),
+            order.Value.to_string(),
+            order.Tag.clone().unwrap_or_default(),
+        ])
+        .collect()
+}
+
+fn pairs(statistics: &[(String, String)]) -> Vec<Vec<String>> {
+    statistics.iter().map(|(name, value)| vec![name.clone(), value.clone()]).collect()
+}
+
+fn monthly_header() -> Vec<&'static str> {
+    std::iter::once("Year")
+        .chain(analytics::MONTHS.iter().copied())
+        .chain(std::iter::once("Total"))
+        .collect()
+}
+
+fn monthly_rows(equity: &[(f64, f64)]) -> Vec<Vec<String>> {
+    analytics::monthly_returns(equity)
+        .iter()
+        .map(|year| std::iter::once(year.year.to_string())
+            .chain(year.months.iter().map(|month| month.map(|r| format!("{:.1}", r)).unwrap_or_default()))
+            .chain(std::iter::once(format!("{:.1}", year.total)))
+            .collect())
+        .collect()
+}
+
+fn markdown_table(header: &[&str], rows: &[Vec<String>]) -> String {
+    let escape = |cell: &str| cell.replace('|', "\\|");
+    let mut md = format!("| {} |\n", header.iter().map(|h| escape(h)).collect::<Vec<String>>().join(" | "));
+    md.push_str(&format!("|{}\n", " --- |".repeat(header.len())));
+    for row in rows {
+        md.push_str(&format!("| {} |\n", row.iter().map(|c| escape(c)).collect::<Vec<String>>().join(" | ")));
+    }
+
+    md
+}
+
+/// Table with an optional header. Cells holding a negative number are marked so they
+/// show in red.
+fn html_table(header: &[&str], rows: &[Vec<String>]) -> String {
+    let mut html = String::from("<table>\n");
+    if !header.is_empty() {
+        let cells = header.iter().map(|h| format!("<th>{}</th>", escape_html(h))).collect::<String>();
+        html.push_str(&format!("<tr>{}</tr>\n", cells));
+    }
+    for row in rows {
+        let cells = row
+            .iter()
+            .map(|cell| match cell.parse::<f64>() {
+                Ok(value) if value < 0.0 => format!("<td class=\"negative\">{}</td>", escape_html(cell)),
+                _ => format!("<td>{}</td>", escape_html(cell))
+            })
+            .collect::<String>();
+        html.push_str(&format!("<tr>{}</tr>\n", cells));
+    }
+    html.push_str("</table>\n");
+
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn percent_labels(values: &[f64]) -> Vec<String> {
+    values.iter().map(|y| format!("{:.1}%", y)).collect()
+}
+
+fn date(x: f64) -> String {
+    chrono::DateTime::from_timestamp(x as i64, 0)
+        .map(|time| time.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+/// Smallest and largest x and y over every series, `None` if they're all empty
+fn bounds(series: &[&[(f64, f64)]]) -> Option<((f64, f64), (f64, f64))> {
+    let mut points = series.iter().flat_map(|s| s.iter()).peekable();
+    points.peek()?;
+
+    Some(points.fold(
+        ((f64::INFINITY, f64::NEG_INFINITY), (f64::INFINITY, f64::NEG_INFINITY)),
+        |((x_min, x_max), (y_min, y_max)), &(x, y)| ((x_min.min(x), x_max.max(x)), (y_min.min(y), y_max.max(y)))
+    ))
+}
+
+/// Plots series on a character grid, earlier series drawn over later ones, with the value
+/// range on the left and the dates below. `labels` formats the value of every row, bottom
+/// first, so it can tell neighbouring rows apart; only the top and bottom ones are shown.
+fn ascii_chart(series: &[&[(f64, f64)]], labels: impl Fn(&[f64]) -> Vec<String>) -> String {
+    let ((x_min, x_max), (y_min, y_max)) = match bounds(series) {
+        Some(bounds) => bounds,
+        None => return String::from("(no data)\n")
+    };
+    let x_span = (x_max - x_min).max(f64::EPSILON);
+    let y_span = (y_max - y_min).max(f64::EPSILON);
+
+    let mut grid = vec![vec![' '; ASCII_WIDTH]; ASCII_HEIGHT];
+    for (data, mark) in series.iter().zip(ASCII_MARKS.iter()).rev() {
+        for &(x, y) in data.iter() {
+            let column = ((x - x_min) / x_span * (ASCII_WIDTH - 1) as f64).round() as usize;
+            let row = ASCII_HEIGHT - 1 - ((y - y_min) / y_span * (ASCII_HEIGHT - 1) as f64).round() as usize;
+            grid[row][column] = *mark;
+        }
+    }
+
+    let mut labels = labels(&ticks(y_min, y_max, ASCII_HEIGHT));
+    let (top, bottom) = (labels.pop().unwrap_or_default(), labels.into_iter().next().unwrap_or_default());
+    let width = top.chars().count().max(bottom.chars().count());
+    let mut chart = String::new();
+    for (i, row) in grid.iter().enumerate() {
+        let label = match i {
+            0 => top.as_str(),
+            i if i == ASCII_HEIGHT - 1 => bottom.as_str(),
+            _ => ""
+        };
+        chart.push_str(&format!("{:>width$} |{}\n", label, row.iter().collect::<String>().trim_end(), width = width));
+    }
+
+    let (start, end) = (date(x_min), date(x_max));
+    chart.push_str(&format!("{:>width$} +{}\n", "", "-".repeat(ASCII_WIDTH), width = width));
+    chart.push_str(&format!("{:>width$}  {}{:>gap$}\n", "", start, end, width = width, gap = ASCII_WIDTH - start.len()));
+
+    chart
+}
+
+/// Draws series as SVG polylines over evenly spaced gridlines, with their values on the left,
+/// the dates below and a legend above. `labels` formats the gridline values, bottom first.
+fn svg_chart(series: &[(&[(f64, f64)], &str)], labels: impl Fn(&[f64]) -> Vec<String>) -> String {
+    let data = series.iter().map(|(data, _)| *data).collect::<Vec<&[(f64, f64)]>>();
+    let ((x_min, x_max), (y_min, y_max)) = match bounds(&data) {
+        Some(bounds) => bounds,
+        None => return String::from("<p>No data</p>\n")
+    };
+    let x_span = (x_max - x_min).max(f64::EPSILON);
+    let y_span = (y_max - y_min).max(f64::EPSILON);
+    let (plot_width, plot_height) = (SVG_WIDTH - SVG_LEFT - 10.0, SVG_HEIGHT - SVG_TOP - SVG_BOTTOM);
+    let bottom = SVG_TOP + plot_height;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\" font-size=\"12\">\n",
+        w = SVG_WIDTH, h = SVG_HEIGHT
+    );
+
+    let values = ticks(y_min, y_max, SVG_LABELS);
+    for (i, label) in labels(&values).iter().enumerate() {
+        let y = bottom - i as f64 / (SVG_LABELS - 1) as f64 * plot_height;
+        svg.push_str(&format!("<line x1=\"{}\" y1=\"{:.1}\" x2=\"{}\" y2=\"{:.1}\" stroke=\"#eee\"/>\n", SVG_LEFT, y, SVG_LEFT + plot_width, y));
+        svg.push_str(&format!("<text x=\"{}\" y=\"{:.1}\" text-anchor=\"end\" dominant-baseline=\"middle\">{}</text>\n", SVG_LEFT - 6.0, y, escape_html(label)));
+    }
+    svg.push_str(&format!(
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"#ccc\"/>\n",
+        SVG_LEFT, SVG_TOP, plot_width, plot_height
+    ));
+
+    for (i, ((data, name), color)) in series.iter().zip(SVG_COLORS.iter()).enumerate() {
+        let points = data
+            .iter()
+            .map(|&(x, y)| format!(
+                "{:.1},{:.1}",
+                SVG_LEFT + (x - x_min) / x_span * plot_width,
+                bottom - (y - y_min) / y_span * plot_height
+            ))
+            .collect::<Vec<String>>()
+            .join(" ");
+        svg.push_str(&format!("<polyline fill=\"none\" stroke=\"{}\" stroke-width=\"1.5\" points=\"{}\"/>\n", color, points));
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"14\" fill=\"{}\">{}</text>\n",
+            SVG_LEFT + 110.0 * i as f64, color, escape_html(name)
+        ));
+    }
+
+    svg.push_str(&format!("<text x=\"{}\" y=\"{}\">{}</text>\n", SVG_LEFT, bottom + 18.0, date(x_min)));
+    svg.push_str(&format!("<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>\n", SVG_LEFT + plot_width, bottom + 18.0, date(x_max)));
+    svg.push_str("</svg>\n");
+
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JAN_15: f64 = 1579046400.0;
+    const JAN_31: f64 = 1580428800.0;
+    const FEB_28: f64 = 1582848000.0;
+
+    const ORDER: &str = r#"{"Id":7,"ContingentId":0,"BrokerId":[],"Symbol":{"Value":"SPY","ID":"SPY R735QTJ8XC9X","Permtick":"SPY"},
+        "Price":10.5,"PriceCurrency":"USD","Time":"2020-01-02T14:31:00Z","CreatedTime":"2020-01-02T14:31:00Z","LastFillTime":null,
+        "LastUpdateTime":null,"CanceledTime":null,"Quantity":-3,"Type":0,"Status":3,"Tag":"a|b <c> & d","SecurityType":1,
+        "Direction":1,"Value":-31.5,"IsMarketable":true,"LimitPrice":null,"StopPrice":null,"StopTriggered":null}"#;
+
+    fn report<'a>(metadata: &'a Metadata, orders: &'a [Order], equity: &'a [(f64, f64)], benchmark: &'a [(f64, f64)]) -> Report<'a> {
+        Report {
+            metadata,
+            statistics: &[],
+            runtime_statistics: &[],
+            orders,
+            equity,
+            benchmark,
+            drawdown: &[],
+            currency: "$"
+        }
+    }
+
+    fn labels(values: &[f64]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn markdown_escapes_pipes() {
+        assert_eq!(markdown_table(&["A|B", "C"], &[vec!["x|y".to_string(), "z".to_string()]]), "| A\\|B | C |\n| --- | --- |\n| x\\|y | z |\n");
+
+        let order: Order = serde_json::from_str(ORDER).unwrap();
+        let metadata = Metadata { name: "Pipe|Test".to_string(), ..Metadata::default() };
+        let md = report(&metadata, &[order], &[], &[]).markdown();
+        assert!(md.starts_with("# Pipe|Test Backtest Report\n"));
+        assert!(md.contains("| 7 | 2020-01-02T14:31:00Z | SPY | MKT | SLD | -3 | 10.5 | Filled | -31.5 | a\\|b <c> & d |\n"));
+    }
+
+    #[test]
+    fn html_escapes_cells() {
+        assert_eq!(escape_html(r#"<a href="x">&</a>"#), "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;");
+
+        let table = html_table(&["<b>"], &[vec!["a < b & c".to_string(), "-1.5".to_string(), "2".to_string()]]);
+        assert_eq!(table, "<table>\n<tr><th>&lt;b&gt;</th></tr>\n\
+            <tr><td>a &lt; b &amp; c</td><td class=\"negative\">-1.5</td><td>2</td></tr>\n</table>\n");
+        assert_eq!(html_table(&[], &[]), "<table>\n</table>\n");
+
+        let order: Order = serde_json::from_str(ORDER).unwrap();
+        let metadata = Metadata { name: "<script>".to_string(), ..Metadata::default() };
+        let html = report(&metadata, &[order], &[], &[]).html();
+        assert!(html.contains("<h1>&lt;script&gt; Backtest Report</h1>"));
+        assert!(html.contains("<td>a|b &lt;c&gt; &amp; d</td>"));
+        assert!(!html.contains("<script>"));
+    }
+
+    #[test]
+    fn empty_equity_has_no_chart() {
+        let metadata = Metadata::default();
+        let report = report(&metadata, &[], &[], &[(JAN_15, 300.0)]);
+
+        assert!(report.rebased_benchmark().is_empty());
+        assert!(monthly_rows(&[]).is_empty());
+
+        let md = report.markdown();
+        assert!(md.contains("## Equity\n\n```\n(no data)\n"));
+        let html = report.html();
+        assert!(html.contains("<h2>Equity</h2>\n<p>No data</p>\n"));
+        assert!(!html.contains("<svg"));
+
+        assert_eq!(report.write(ReportFormat::None, "unused").unwrap(), None);
+    }
+
+    #[test]
+    fn bounds_over_every_series() {
+        assert_eq!(bounds(&[]), None);
+        assert_eq!(bounds(&[&[], &[]]), None);
+        assert_eq!(bounds(&[&[], &[(1.0, 5.0), (3.0, 2.0)], &[(2.0, 7.0)]]), Some(((1.0, 3.0), (2.0, 7.0))));
+    }
+
+    #[test]
+    fn ascii_chart_marks_and_labels() {
+        assert_eq!(ascii_chart(&[], labels), "(no data)\n");
+        assert_eq!(ascii_chart(&[&[]], labels), "(no data)\n");
+
+        let rising = [(JAN_15, 1.0), (FEB_28, 2.0)];
+        let falling = [(JAN_15, 2.0), (FEB_28, 1.0)];
+        let chart = ascii_chart(&[&rising, &falling], labels);
+        let lines = chart.lines().collect::<Vec<&str>>();
+
+        assert_eq!(lines.len(), ASCII_HEIGHT + 2);
+        assert_eq!(lines[0], format!("2 |.{}*", " ".repeat(ASCII_WIDTH - 2)));
+        assert_eq!(lines[1], "  |");
+        assert_eq!(lines[ASCII_HEIGHT - 1], format!("1 |*{}.", " ".repeat(ASCII_WIDTH - 2)));
+        assert_eq!(lines[ASCII_HEIGHT], format!("  +{}", "-".repeat(ASCII_WIDTH)));
+        assert_eq!(lines[ASCII_HEIGHT + 1], format!("   2020-01-15{:>w$}", "2020-02-28", w = ASCII_WIDTH - 10));
+
+        // Earlier series are drawn over later ones
+        let chart = ascii_chart(&[&rising, &rising], labels);
+        assert!(!chart.contains('.'));
+    }
+
+    #[test]
+    fn ascii_labels_tell_rows_apart() {
+        // The label step is one row rather than the whole range, which would leave no decimals
+        let chart = ascii_chart(&[&[(JAN_15, 1000.0), (FEB_28, 1001.5)]], |values| value_labels(values, "$"));
+        assert!(chart.starts_with("$1,001.50 |"));
+        assert!(chart.contains("\n$1,000.00 |*\n"));
+    }
+
+    #[test]
+    fn svg_chart_two_series() {
+        assert_eq!(svg_chart(&[], labels), "<p>No data</p>\n");
+        assert_eq!(svg_chart(&[(&[], "Empty")], labels), "<p>No data</p>\n");
+
+        let equity = [(JAN_15, 0.0), (FEB_28, 100.0)];
+        let benchmark = [(JAN_15, 50.0)];
+        let svg = svg_chart(&[(&equity, "Equity"), (&benchmark, "S&P")], labels);
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        assert!(svg.contains("<polyline fill=\"none\" stroke=\"#1f77b4\" stroke-width=\"1.5\" points=\"90.0,230.0 890.0,20.0\"/>"));
+        assert!(svg.contains("<polyline fill=\"none\" stroke=\"#ff7f0e\" stroke-width=\"1.5\" points=\"90.0,125.0\"/>"));
+        assert!(svg.contains(">Equity</text>"));
+        assert!(svg.contains(">S&amp;P</text>"));
+        assert_eq!(svg.matches("<line ").count(), SVG_LABELS);
+        for label in ["0", "25", "50", "75", "100"] {
+            assert!(svg.contains(&format!("dominant-baseline=\"middle\">{}</text>", label)));
+        }
+        assert!(svg.contains(">2020-01-15</text>"));
+        assert!(svg.contains(">2020-02-28</text>"));
+    }
+
+    #[test]
+    fn monthly_rows_formatted() {
+        let rows = monthly_rows(&[(JAN_15, 100.0), (JAN_31, 110.0), (FEB_28, 99.0)]);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].len(), monthly_header().len());
+        assert_eq!(rows[0][..4], ["2020", "10.0", "-10.0", ""]);
+        assert_eq!(rows[0][13], "-1.0");
+    }
+
+    #[test]
+    fn benchmark_rebased_to_equity() {
+        let metadata = Metadata::default();
+        let equity = [(JAN_31, 1000.0), (FEB_28, 1100.0)];
+        let benchmark = [(JAN_15, 40.0), (JAN_31, 50.0), (FEB_28, 45.0)];
+
+        let rebased = report(&metadata, &[], &equity, &benchmark).rebased_benchmark();
+        assert_eq!(rebased, vec![(JAN_15, 800.0), (JAN_31, 1000.0), (FEB_28, 900.0)]);
+        assert!(report(&metadata, &[], &equity, &[]).rebased_benchmark().is_empty());
+    }
+}
@@ -0,0 +1,573 @@
+use std::cell::Cell;
+use crossterm::event::{KeyCode, MouseButton, MouseEvent};
+use tui::{layout::Rect, text::Span};
+
+use crate::{analytics::{ReturnPeriod, RiskMetric}, config::{HistogramConfig, RollingConfig}};
+
+/// Fraction of the visible window the chart moves by per pan
+const PAN_STEP: f64 = 0.1;
+
+/// Factor applied to the visible window per zoom step
+const ZOOM_STEP: f64 = 0.8;
+
+/// Interactive state of a time series chart: visible window, crosshair and mouse selection
+#[derive(Clone, Debug, Default)]
+pub struct ChartView {
+    /// Visible x range, `None` fits all of the data
+    pub window: Option<(f64, f64)>,
+    /// Index of the data point under the crosshair
+    pub cursor: Option<usize>,
+    /// Columns where the current mouse drag started and currently is
+    pub selection: Option<(u16, u16)>,
+    /// Plot area from the last render, used to map mouse columns back to x values
+    pub plot: Cell<Rect>,
+    /// Whether y values are plotted on a logarithmic scale
+    pub log_scale: bool,
+}
+
+impl ChartView {
+    /// Visible x bounds for `data`, which must be sorted by x
+    pub fn bounds(&self, data: &[(f64, f64)]) -> Option<(f64, f64)> {
+        if data.is_empty() {
+            return None
+        }
+
+        Some(self.window.unwrap_or((data[0].0, data[data.len() - 1].0)))
+    }
+
+    /// Points of `data` inside the visible window
+    pub fn visible<'a>(&self, data: &'a [(f64, f64)]) -> &'a [(f64, f64)] {
+        match self.window {
+            Some((lo, hi)) => {
+                let start = data.partition_point(|p| p.0 < lo);
+                let end = data.partition_point(|p| p.0 <= hi);
+                &data[start..end.max(start)]
+            },
+            None => data
+        }
+    }
+
+    /// Maps a y value into plot space, `None` if it can't be shown on the current scale
+    pub fn scale_y(&self, y: f64) -> Option<f64> {
+        match self.log_scale {
+            true if y > 0.0 => Some(y.ln()),
+            true => None,
+            false => Some(y)
+        }
+    }
+
+    /// Maps a y value in plot space back to the real value, for labels
+    pub fn unscale_y(&self, y: f64) -> f64 {
+        if self.log_scale { y.exp() } else { y }
+    }
+
+    /// Transforms points into plot space. Non-positive values have no logarithm, so on a log
+    /// scale they are dropped and their count is returned so it can be shown to the user.
+    pub fn transform(&self, data: &[(f64, f64)]) -> (Vec<(f64, f64)>, usize) {
+        let points = data
+            .iter()
+            .filter_map(|&(x, y)| self.scale_y(y).map(|y| (x, y)))
+            .collect::<Vec<(f64, f64)>>();
+        let hidden = data.len() - points.len();
+
+        (points, hidden)
+    }
+
+    /// Evenly spaced y labels for plot space bounds, showing the real values
+    pub fn y_labels(&self, lo: f64, hi: f64, count: usize, symbol: &str) -> Vec<String> {
+        let values = ticks(lo, hi, count)
+            .into_iter()
+            .map(|y| self.unscale_y(y))
+            .collect::<Vec<f64>>();
+
+        value_labels(&values, symbol)
+    }
+
+    /// Handles a chart key, returning false if the key isn't one of ours
+    pub fn key(&mut self, code: KeyCode, data: &[(f64, f64)]) -> bool {
+        match code {
+            KeyCode::Char('+') | KeyCode::Char('=') => self.zoom(ZOOM_STEP, data),
+            KeyCode::Char('-') => self.zoom(1.0 / ZOOM_STEP, data),
+            KeyCode::Char('h') => self.pan(-PAN_STEP, data),
+            KeyCode::Char('l') => self.pan(PAN_STEP, data),
+            KeyCode::Left => self.move_cursor(-1, data),
+            KeyCode::Right => self.move_cursor(1, data),
+            KeyCode::Char('c') => self.cursor = match self.cursor {
+                Some(_) => None,
+                None => self.last_visible(data)
+            },
+            KeyCode::Char('0') => self.reset(),
+            KeyCode::Char('y') => self.log_scale = !self.log_scale,
+            _ => return false
+        }
+
+        true
+    }
+
+    /// Handles mouse events over the plot: wheel zooms, click places the crosshair and dragging
+    /// selects the range to zoom into. Returns false if the event is outside of the plot.
+    pub fn mouse(&mut self, event: MouseEvent, data: &[(f64, f64)]) -> bool {
+        let plot = self.plot.get();
+        let inside = |column: u16, row: u16| {
+            column >= plot.left() && column < plot.right() && row >= plot.top() && row < plot.bottom()
+        };
+
+        match event {
+            MouseEvent::Down(MouseButton::Left, column, row, _) if inside(column, row) => {
+                self.selection = Some((column, column));
+            },
+            MouseEvent::Drag(MouseButton::Left, column, _, _) if self.selection.is_some() => {
+                let start = self.selection.unwrap().0;
+                self.selection = Some((start, column.max(plot.left()).min(plot.right().saturating_sub(1))));
+            },
+            MouseEvent::Up(MouseButton::Left, _, _, _) if self.selection.is_some() => {
+                let (start, end) = self.selection.take().unwrap();
+                let (from, to) = (self.x_at(start.min(end), data), self.x_at(start.max(end), data));
+
+                match (from, to) {
+                    (Some(from), Some(to)) if end != start => self.window = Some((from, to)),
+                    (Some(x), _) => self.cursor = Some(nearest(data, x)),
+                    _ => {}
+                }
+            },
+            MouseEvent::ScrollUp(column, row, _) if inside(column, row) => self.zoom(ZOOM_STEP, data),
+            MouseEvent::ScrollDown(column, row, _) if inside(column, row) => self.zoom(1.0 / ZOOM_STEP, data),
+            _ => return false
+        }
+
+        true
+    }
+
+    /// Maps a terminal column inside the plot to its x value
+    pub fn x_at(&self, column: u16, data: &[(f64, f64)]) -> Option<f64> {
+        let plot = self.plot.get();
+        let (lo, hi) = self.bounds(data)?;
+        if plot.width < 2 {
+            return None
+        }
+
+        let offset = column.saturating_sub(plot.left()).min(plot.width - 1);
+        Some(lo + (hi - lo) * offset as f64 / (plot.width - 1) as f64)
+    }
+
+    pub fn reset(&mut self) {
+        self.window = None;
+        self.selection = None;
+    }
+
+    fn zoom(&mut self, factor: f64, data: &[(f64, f64)]) {
+        let (first, last) = match self.full_bounds(data) {
+            Some(b) => b,
+            None => return
+        };
+        let (lo, hi) = self.bounds(data).unwrap();
+        let center = self.cursor.map(|i| data[i].0).unwrap_or((lo + hi) / 2.0);
+        let width = (hi - lo) * factor;
+
+        if width >= last - first {
+            self.window = None;
+            return
+        }
+
+        // Keep the zoom center at the same relative position while staying inside the data
+        let ratio = if hi > lo { (center - lo) / (hi - lo) } else { 0.5 };
+        let lo = (center - width * ratio).max(first).min(last - width);
+        self.window = Some((lo, lo + width));
+
+        // Never zoom in past the two points around the center, a single point can't be plotted
+        if self.visible(data).len() < 2 {
+            let i = data.partition_point(|p| p.0 < center).max(1).min(data.len() - 1);
+            self.window = Some((data[i - 1].0, data[i].0));
+        }
+        self.clamp_cursor(data);
+    }
+
+    fn pan(&mut self, fraction: f64, data: &[(f64, f64)]) {
+        let (first, last) = match self.full_bounds(data) {
+            Some(b) => b,
+            None => return
+        };
+
+        if let Some((lo, hi)) = self.window {
+            let width = hi - lo;
+            let lo = (lo + width * fraction).max(first).min(last - width);
+            self.window = Some((lo, lo + width));
+            self.clamp_cursor(data);
+        }
+    }
+
+    fn move_cursor(&mut self, delta: isize, data: &[(f64, f64)]) {
+        let cursor = match self.cursor {
+            Some(i) => (i as isize + delta).max(0).min(data.len() as isize - 1) as usize,
+            None => match self.last_visible(data) {
+                Some(i) => i,
+                None => return
+            }
+        };
+        self.cursor = Some(cursor);
+
+        // Scroll the window along when the crosshair walks off of it
+        if let Some((lo, hi)) = self.window {
+            let x = data[cursor].0;
+            if x < lo || x > hi {
+                let shift = if x < lo { x - lo } else { x - hi };
+                self.window = Some((lo + shift, hi + shift));
+            }
+        }
+    }
+
+    /// Keeps the crosshair on a visible point after the window changes
+    fn clamp_cursor(&mut self, data: &[(f64, f64)]) {
+        if let (Some(i), Some((lo, hi))) = (self.cursor, self.window) {
+            let x = data[i].0.max(lo).min(hi);
+            self.cursor = Some(nearest(data, x));
+        }
+    }
+
+    fn last_visible(&self, data: &[(f64, f64)]) -> Option<usize> {
+        let (_, hi) = self.bounds(data)?;
+        Some(nearest(data, hi))
+    }
+
+    fn full_bounds(&self, data: &[(f64, f64)]) -> Option<(f64, f64)> {
+        if data.len() < 2 {
+            return None
+        }
+
+        Some((data[0].0, data[data.len() - 1].0))
+    }
+}
+
+/// Index of the point closest to `x` in data sorted by x
+pub fn nearest(data: &[(f64, f64)], x: f64) -> usize {
+    let i = data.partition_point(|p| p.0 < x);
+    if i == 0 {
+        return 0
+    }
+    if i >= data.len() {
+        return data.len() - 1
+    }
+
+    if (data[i].0 - x).abs() < (x - data[i - 1].0).abs() { i } else { i - 1 }
+}
+
+/// Formats a Unix timestamp from `ChartPoint.x`
+pub fn format_timestamp(x: f64, format: &str) -> String {
+    match chrono::DateTime::from_timestamp(x as i64, 0) {
+        Some(time) => time.format(format).to_string(),
+        None => x.to_string()
+    }
+}
+
+/// Date format whose granularity fits the visible span: time of day, day, month or year
+pub fn time_format(span: f64) -> &'static str {
+    const DAY: f64 = 86400.0;
+    match span {
+        s if s <= DAY => "%H:%M",
+        s if s <= 10.0 * DAY => "%m-%d %H:%M",
+        s if s <= 366.0 * DAY => "%Y-%m-%d",
+        s if s <= 5.0 * 366.0 * DAY => "%b %Y",
+        _ => "%Y"
+    }
+}
+
+/// `count` evenly spaced values from `lo` to `hi`, matching where tui places axis labels
+pub fn ticks(lo: f64, hi: f64, count: usize) -> Vec<f64> {
+    if count < 2 {
+        return vec![lo]
+    }
+
+    (0..count).map(|i| lo + (hi - lo) * i as f64 / (count - 1) as f64).collect()
+}
+
+/// Evenly spaced date labels for Unix timestamps between `lo` and `hi`
+pub fn time_labels(lo: f64, hi: f64, count: usize) -> Vec<String> {
+    let format = time_format(hi - lo);
+    ticks(lo, hi, count).into_iter().map(|x| format_timestamp(x, format)).collect()
+}
+
+/// Currency labels for ascending `values`, with enough decimals to tell the closest two apart
+pub fn value_labels(values: &[f64], symbol: &str) -> Vec<String> {
+    let step = values
+        .windows(2)
+        .map(|w| (w[1] - w[0]).abs())
+        .fold(f64::INFINITY, f64::min);
+    let step = if step.is_finite() { step } else { 0.0 };
+    let max = values.iter().fold(0f64, |a, b| a.max(b.abs()));
+
+    values.iter().map(|&y| crate::format::compact_currency(y, step, max, symbol)).collect()
+}
+
+/// How many labels of `label_width` columns fit along `length` with some breathing room
+pub fn label_count(length: u16, label_width: u16, max: usize) -> usize {
+    ((length / (label_width + 4).max(1)) as usize).max(2).min(max)
+}
+
+/// Replicates the layout `tui::widgets::Chart` uses to find where points are drawn inside the
+/// chart's inner area once the axis labels are placed
+pub fn plot_area(inner: Rect, x_labels: &[Span], y_labels: &[Span]) -> Rect {
+    if inner.height == 0 || inner.width == 0 {
+        return Rect::default()
+    }
+
+    let mut x = inner.left();
+    let mut y = inner.bottom() - 1;
+    let has_x_labels = !x_labels.is_empty();
+    let has_y_labels = !y_labels.is_empty();
+
+    if has_x_labels && y > inner.top() {
+        y -= 1;
+    }
+
+    if has_y_labels {
+        let mut max_width = y_labels.iter().map(Span::width).max().unwrap_or_default() as u16;
+        if let Some(first) = x_labels.first() {
+            max_width = max_width.max(first.width() as u16);
+        }
+        if x + max_width < inner.right() {
+            x += max_width;
+        }
+    }
+
+    if has_x_labels && y > inner.top() {
+        y -= 1;
+    }
+
+    if has_y_labels && x + 1 < inner.right() {
+        x += 1;
+    }
+
+    if x < inner.right() && y > 1 {
+        Rect::new(x, inner.top(), inner.right() - x, y - inner.top() + 1)
+    } else {
+        Rect::default()
+    }
+}
+
+/// Smallest bucket the histogram can be narrowed to, in percent
+const MIN_BUCKET_WIDTH: f64 = 0.01;
+
+/// Runtime settings of the returns histogram
+#[derive(Clone, Debug, Default)]
+pub struct HistogramView {
+    pub period: ReturnPeriod,
+    /// Requested bucket width in percent. Rendering doubles it until the buckets fit.
+    pub bucket_width: f64,
+}
+
+impl HistogramView {
+    pub fn new(config: &HistogramConfig) -> Self {
+        Self {
+            period: config.period,
+            bucket_width: config.bucket_width.max(MIN_BUCKET_WIDTH)
+        }
+    }
+
+    /// Handles a histogram key, returning false if the key isn't one of ours
+    pub fn key(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Char('p') => self.period = self.period.next(),
+            KeyCode::Char('[') => self.bucket_width = (self.bucket_width / 2.0).max(MIN_BUCKET_WIDTH),
+            KeyCode::Char(']') => self.bucket_width *= 2.0,
+            _ => return false
+        }
+
+        true
+    }
+}
+
+/// Window lengths `w` cycles through: a month, a quarter, half a year and a year of trading days
+const ROLLING_WINDOWS: [usize; 4] = [21, 63, 126, 252];
+
+/// Runtime settings of the rolling risk metrics chart
+#[derive(Clone, Debug, Default)]
+pub struct RollingView {
+    pub metric: RiskMetric,
+    /// Number of daily returns in each window
+    pub window: usize,
+}
+
+impl RollingView {
+    pub fn new(config: &RollingConfig) -> Self {
+        Self {
+            metric: config.metric,
+            window: config.window.max(2)
+        }
+    }
+
+    /// Handles a rolling chart key, returning false if the key isn't one of ours
+    pub fn key(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Char('r') => self.metric = self.metric.next(),
+            KeyCode::Char('w') => {
+                self.window = ROLLING_WINDOWS
+                    .iter()
+                    .copied()
+                    .find(|&w| w > self.window)
+                    .unwrap_or(ROLLING_WINDOWS[0])
+            },
+            _ => return false
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::assert_close;
+
+    fn data() -> Vec<(f64, f64)> {
+        (0..11).map(|i| (i as f64 * 10.0, i as f64)).collect()
+    }
+
+    #[test]
+    fn zoom_keeps_center_and_resets_when_fully_out() {
+        let data = data();
+        let mut view = ChartView::default();
+
+        view.zoom(0.5, &data);
+        let (lo, hi) = view.window.unwrap();
+        assert_close(lo, 25.0);
+        assert_close(hi, 75.0);
+
+        view.zoom(4.0, &data);
+        assert_eq!(view.window, None);
+    }
+
+    #[test]
+    fn zoom_stops_at_two_points() {
+        let data = data();
+        let mut view = ChartView { cursor: Some(4), ..ChartView::default() };
+
+        for _ in 0..50 {
+            view.zoom(ZOOM_STEP, &data);
+        }
+
+        assert_eq!(view.visible(&data).len(), 2);
+        let (lo, hi) = view.window.unwrap();
+        assert!(lo <= 40.0 && hi >= 40.0);
+        assert!(hi - lo >= 10.0);
+        assert_eq!(view.cursor, Some(4));
+    }
+
+    #[test]
+    fn zoom_and_pan_need_two_points() {
+        let mut view = ChartView::default();
+        view.zoom(0.5, &[(1.0, 1.0)]);
+        view.pan(PAN_STEP, &[]);
+        assert_eq!(view.window, None);
+    }
+
+    #[test]
+    fn pan_stays_inside_the_data() {
+        let data = data();
+        let mut view = ChartView { window: Some((20.0, 40.0)), ..ChartView::default() };
+
+        view.pan(0.5, &data);
+        assert_eq!(view.window, Some((30.0, 50.0)));
+
+        for _ in 0..20 {
+            view.pan(-0.5, &data);
+        }
+        assert_eq!(view.window, Some((0.0, 20.0)));
+
+        for _ in 0..20 {
+            view.pan(0.5, &data);
+        }
+        assert_eq!(view.window, Some((80.0, 100.0)));
+
+        // Panning without a window has nothing to move
+        let mut view = ChartView::default();
+        view.pan(0.5, &data);
+        assert_eq!(view.window, None);
+    }
+
+    #[test]
+    fn nearest_point() {
+        let data = data();
+        assert_eq!(nearest(&data, -5.0), 0);
+        assert_eq!(nearest(&data, 14.0), 1);
+        assert_eq!(nearest(&data, 16.0), 2);
+        assert_eq!(nearest(&data, 20.0), 2);
+        assert_eq!(nearest(&data, 500.0), 10);
+        assert_eq!(nearest(&[], 1.0), 0);
+    }
+
+    #[test]
+    fn x_at_maps_columns_across_the_window() {
+        let data = data();
+        let view = ChartView::default();
+        view.plot.set(Rect::new(10, 0, 11, 5));
+
+        assert_eq!(view.x_at(10, &data), Some(0.0));
+        assert_eq!(view.x_at(15, &data), Some(50.0));
+        assert_eq!(view.x_at(20, &data), Some(100.0));
+        // Columns outside of the plot clamp to its edges
+        assert_eq!(view.x_at(0, &data), Some(0.0));
+        assert_eq!(view.x_at(40, &data), Some(100.0));
+
+        let view = ChartView { window: Some((20.0, 40.0)), ..ChartView::default() };
+        view.plot.set(Rect::new(10, 0, 11, 5));
+        assert_eq!(view.x_at(15, &data), Some(30.0));
+
+        assert_eq!(view.x_at(15, &[]), None);
+        view.plot.set(Rect::new(10, 0, 1, 5));
+        assert_eq!(view.x_at(10, &data), None);
+    }
+
+    #[test]
+    fn ticks_evenly_spaced() {
+        assert_eq!(ticks(0.0, 10.0, 3), vec![0.0, 5.0, 10.0]);
+        assert_eq!(ticks(-10.0, 10.0, 5), vec![-10.0, -5.0, 0.0, 5.0, 10.0]);
+        assert_eq!(ticks(5.0, 5.0, 2), vec![5.0, 5.0]);
+        assert_eq!(ticks(1.0, 2.0, 1), vec![1.0]);
+        assert_eq!(ticks(1.0, 2.0, 0), vec![1.0]);
+    }
+
+    #[test]
+    fn time_format_fits_the_span() {
+        const DAY: f64 = 86400.0;
+        assert_eq!(time_format(5.0 * 60.0), "%H:%M");
+        assert_eq!(time_format(DAY), "%H:%M");
+        assert_eq!(time_format(2.0 * DAY), "%m-%d %H:%M");
+        assert_eq!(time_format(30.0 * DAY), "%Y-%m-%d");
+        assert_eq!(time_format(2.0 * 365.0 * DAY), "%b %Y");
+        assert_eq!(time_format(10.0 * 365.0 * DAY), "%Y");
+    }
+
+    #[test]
+    fn value_labels_tell_neighbours_apart() {
+        assert_eq!(value_labels(&[0.0, 0.5, 1.0], "$"), vec!["$0.0", "$0.5", "$1.0"]);
+        assert_eq!(value_labels(&[-20_000.0, 0.0, 20_000.0], "$"), vec!["-$20k", "$0k", "$20k"]);
+        assert_eq!(value_labels(&[7.0], "$"), vec!["$7"]);
+    }
+
+    #[test]
+    fn log_scale_drops_non_positive_values() {
+        let data = [(1.0, 100.0), (2.0, 0.0), (3.0, -5.0), (4.0, 1.0)];
+        let view = ChartView { log_scale: true, ..ChartView::default() };
+
+        let (points, hidden) = view.transform(&data);
+        assert_eq!(hidden, 2);
+        assert_eq!(points.len(), 2);
+        assert_close(points[0].1, 100f64.ln());
+        assert_close(points[1].1, 0.0);
+
+        let linear = ChartView::default();
+        assert_eq!(linear.transform(&data), (data.to_vec(), 0));
+    }
+
+    #[test]
+    fn log_scale_labels_show_real_values() {
+        let view = ChartView { log_scale: true, ..ChartView::default() };
+        assert_close(view.unscale_y(view.scale_y(250.0).unwrap()), 250.0);
+
+        let labels = view.y_labels(1e4f64.ln(), 1e6f64.ln(), 3, "$");
+        assert_eq!(labels, vec!["$10k", "$100k", "$1,000k"]);
+
+        let linear = ChartView::default();
+        assert_eq!(linear.y_labels(0.0, 100.0, 3, "$"), vec!["$0", "$50", "$100"]);
+    }
+}
@@ -0,0 +1,190 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use crossterm::event::KeyCode;
+
+use super::logs::{LogLevel, LogRecord};
+
+/// Prefix Lean puts in front of exceptions thrown by the algorithm
+const RUNTIME_ERROR: &str = "Runtime Error";
+
+/// An error reported by the algorithm, with the stack trace that came with it
+#[derive(Clone, Debug, Default)]
+pub struct ErrorEntry {
+    /// Time in the algorithm when the error was written, if Lean prefixed it
+    pub algorithm_time: Option<NaiveDateTime>,
+    /// Wall-clock time the error reached us
+    pub received: DateTime<Utc>,
+    /// Type of the exception, e.g. `System.NullReferenceException`, if the message names one.
+    /// Runtime errors that don't name a type are reported as `Runtime Error`.
+    pub exception: Option<String>,
+    /// First line of the error without the exception type
+    pub message: String,
+    /// Stack frames and any other line following the first
+    pub trace: Vec<String>,
+}
+
+impl ErrorEntry {
+    /// Builds an entry from the lines of a single message. Lines after the first make up
+    /// the stack trace.
+    pub fn parse(msg: &str, received: DateTime<Utc>) -> Self {
+        let mut lines = msg.lines().filter(|line| !line.trim().is_empty());
+        let headline = LogRecord::parse(lines.next().unwrap_or_default(), LogLevel::Error, received);
+        let (exception, message) = split_exception(&headline.message);
+
+        Self {
+            algorithm_time: headline.algorithm_time,
+            received,
+            exception: exception.map(str::to_string),
+            message: message.to_string(),
+            trace: lines.map(|line| line.trim_end().to_string()).collect()
+        }
+    }
+
+    /// Exception type without its namespace, e.g. `NullReferenceException`
+    pub fn exception_name(&self) -> Option<&str> {
+        self.exception.as_deref().map(|name| name.rsplit('.').next().unwrap_or(name))
+    }
+
+    /// Algorithm time as Lean writes it, or an empty string
+    pub fn time(&self) -> String {
+        LogRecord { algorithm_time: self.algorithm_time, ..LogRecord::default() }.time()
+    }
+}
+
+/// Whether a message belongs in the error panel: anything sent through `error`, and the
+/// runtime errors Lean reports through the other log levels
+pub fn is_error(msg: &str, level: LogLevel) -> bool {
+    level == LogLevel::Error || msg.lines().any(|line| line.contains(RUNTIME_ERROR))
+}
+
+/// Whether every line of a message is part of a stack trace, meaning it continues the
+/// previous error rather than starting a new one
+pub fn is_trace(msg: &str) -> bool {
+    msg.lines()
+        .filter(|line| !line.trim().is_empty())
+        .all(|line| {
+            let trimmed = line.trim_start();
+            trimmed.len() < line.len() || trimmed.starts_with("at ") || trimmed.starts_with("--- ")
+        })
+}
+
+/// Splits `Runtime Error: System.Exception: message` into the exception type and message.
+/// The runtime error prefix gives way to a more specific type, and only names ending in
+/// `Exception` or `Error` without spaces count as one.
+fn split_exception(line: &str) -> (Option<&str>, &str) {
+    let mut message = line.trim();
+    let mut exception = None;
+    while let Some((head, rest)) = message.split_once(": ") {
+        let is_type = !head.contains(char::is_whitespace) && (head.ends_with("Exception") || head.ends_with("Error"));
+        if head == RUNTIME_ERROR && exception.is_none() {
+            exception = Some(RUNTIME_ERROR);
+            message = rest;
+        } else if is_type && exception.is_none_or(|e| e == RUNTIME_ERROR) {
+            exception = Some(head);
+            message = rest;
+        } else {
+            break
+        }
+    }
+
+    (exception, message)
+}
+
+/// Selection and expanded entries of the error panel
+#[derive(Clone, Debug, Default)]
+pub struct ErrorView {
+    /// Index of the selected entry
+    pub selected: usize,
+    /// Entries whose stack trace is shown
+    pub expanded: Vec<usize>,
+}
+
+impl ErrorView {
+    pub fn is_expanded(&self, entry: usize) -> bool {
+        self.expanded.contains(&entry)
+    }
+
+    /// Handles an error panel key, returning false if the key isn't one of ours
+    pub fn key(&mut self, code: KeyCode, len: usize) -> bool {
+        match code {
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => self.selected = (self.selected + 1).min(len.saturating_sub(1)),
+            KeyCode::Home => self.selected = 0,
+            KeyCode::End => self.selected = len.saturating_sub(1),
+            KeyCode::Enter | KeyCode::Char(' ') if len > 0 => {
+                match self.expanded.iter().position(|&i| i == self.selected) {
+                    Some(i) => { self.expanded.remove(i); },
+                    None => self.expanded.push(self.selected)
+                }
+            },
+            KeyCode::Char('c') => self.expanded.clear(),
+            _ => return false
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RUNTIME: &str = "2020-01-03 10:00:00 : Runtime Error: System.NullReferenceException: Object reference not set to an instance of an object.\n  at QuantConnect.Algorithm.CSharp.MyAlgo.OnData (QuantConnect.Data.Slice data) [0x00010] in <abc>:0\n\n  at QuantConnect.Lean.Engine.AlgorithmManager.Run () [0x00001] in <def>:0";
+
+    #[test]
+    fn parse_runtime_error_with_trace() {
+        let entry = ErrorEntry::parse(RUNTIME, Utc::now());
+
+        assert_eq!(entry.time(), "2020-01-03 10:00:00");
+        assert_eq!(entry.exception.as_deref(), Some("System.NullReferenceException"));
+        assert_eq!(entry.exception_name(), Some("NullReferenceException"));
+        assert_eq!(entry.message, "Object reference not set to an instance of an object.");
+        // Blank lines are dropped and frames keep their indentation
+        assert_eq!(entry.trace, vec![
+            "  at QuantConnect.Algorithm.CSharp.MyAlgo.OnData (QuantConnect.Data.Slice data) [0x00010] in <abc>:0",
+            "  at QuantConnect.Lean.Engine.AlgorithmManager.Run () [0x00001] in <def>:0",
+        ]);
+    }
+
+    #[test]
+    fn parse_plain_error() {
+        let entry = ErrorEntry::parse("Order error: insufficient buying power", Utc::now());
+        assert_eq!(entry.algorithm_time, None);
+        assert_eq!(entry.exception, None);
+        assert_eq!(entry.message, "Order error: insufficient buying power");
+        assert!(entry.trace.is_empty());
+
+        let entry = ErrorEntry::parse("", Utc::now());
+        assert_eq!(entry.message, "");
+        assert!(entry.trace.is_empty());
+    }
+
+    #[test]
+    fn errors_by_level_or_runtime_prefix() {
+        assert!(is_error(RUNTIME, LogLevel::Trace));
+        assert!(is_error("anything", LogLevel::Error));
+        // Mentioning an error isn't enough outside of the error level
+        assert!(!is_error("Error handling: retrying the request", LogLevel::Log));
+        assert!(!is_error("No errors found", LogLevel::Debug));
+    }
+
+    #[test]
+    fn trace_lines_continue_an_error() {
+        assert!(is_trace("  at QuantConnect.Lean.Engine.AlgorithmManager.Run () [0x00001] in <def>:0"));
+        assert!(is_trace("at A.B ()\n  at C.D ()\n\n"));
+        assert!(is_trace("--- End of inner exception stack trace ---"));
+        assert!(!is_trace(RUNTIME));
+        assert!(!is_trace("at A.B ()\nRuntime Error: boom"));
+        assert!(!is_trace("Error: something else"));
+    }
+
+    #[test]
+    fn split_exception_types() {
+        assert_eq!(split_exception("Runtime Error: System.Exception: boom"), (Some("System.Exception"), "boom"));
+        assert_eq!(split_exception("Runtime Error: Trying to retrieve an element"), (Some(RUNTIME_ERROR), "Trying to retrieve an element"));
+        assert_eq!(split_exception("System.ArgumentError: bad: value"), (Some("System.ArgumentError"), "bad: value"));
+        // Only the first type counts, later ones are part of the message
+        assert_eq!(split_exception("KeyException: InnerException: x"), (Some("KeyException"), "InnerException: x"));
+        assert_eq!(split_exception("Order error: rejected"), (None, "Order error: rejected"));
+        assert_eq!(split_exception("  no colon  "), (None, "no colon"));
+    }
+}
@@ -0,0 +1,427 @@
+use std::{cell::RefCell, collections::{VecDeque, hash_map::DefaultHasher}, hash::{Hash, Hasher}, fs::{self, File, OpenOptions}, io::{self, BufRead, BufReader, BufWriter, Seek, SeekFrom, Write}, path::PathBuf};
+use chrono::{DateTime, NaiveDateTime};
+
+use crate::config::LogConfig;
+use super::logs::{ALGORITHM_TIME_FORMAT, LogLevel, LogRecord};
+
+/// Number of spilled records read back from disk at once
+const PAGE_SIZE: usize = 256;
+
+/// What the log panel needs to filter and group a record without reading its message
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RecordInfo {
+    pub level: LogLevel,
+    /// Hash of the level and the message with its numbers masked, equal for lines that only
+    /// differ in quantities, prices or ids
+    pub template: u64,
+    /// Number of identical lines the record stands for
+    pub count: usize,
+}
+
+impl RecordInfo {
+    fn new(record: &LogRecord) -> Self {
+        let mut hasher = DefaultHasher::new();
+        record.level.name().hash(&mut hasher);
+        template(&record.message).hash(&mut hasher);
+
+        Self {
+            level: record.level,
+            template: hasher.finish(),
+            count: record.count
+        }
+    }
+}
+
+/// Where a spilled record lives on disk
+#[derive(Clone, Copy, Debug)]
+struct Spilled {
+    /// Generation of the file, which is part of its name
+    file: u64,
+    /// Byte offset of the record's line in the file
+    offset: u64,
+    info: RecordInfo,
+}
+
+/// Log records of the run. The newest `capacity` records are kept in memory, older ones are
+/// written to rotating files and read back a page at a time when scrolled to.
+///
+/// Records are addressed by position, oldest first. Positions only shift when a rotated file
+/// is deleted, which `push` reports.
+#[derive(Clone, Debug)]
+pub struct LogStore {
+    config: LogConfig,
+    /// Records still in memory, following the spilled ones
+    memory: VecDeque<(RecordInfo, LogRecord)>,
+    /// Records in the files that haven't been rotated away
+    spilled: VecDeque<Spilled>,
+    /// Generation of the file being written
+    generation: u64,
+    /// Size of the file being written
+    written: u64,
+    /// Set once writing to disk failed, after which old records are dropped instead
+    disabled: bool,
+    /// Number of lines of each level, in `LogLevel::ALL` order
+    counts: [usize; 4],
+    /// Records ever dropped from the front, so cached positions can tell they're stale
+    dropped: usize,
+    /// Last page read from disk and the position of its first record
+    page: RefCell<(usize, Vec<LogRecord>)>,
+}
+
+impl Default for LogStore {
+    fn default() -> Self {
+        Self::new(&LogConfig::default())
+    }
+}
+
+impl LogStore {
+    pub fn new(config: &LogConfig) -> Self {
+        Self {
+            config: config.clone(),
+            memory: VecDeque::new(),
+            spilled: VecDeque::new(),
+            generation: 0,
+            written: 0,
+            disabled: false,
+            counts: [0; 4],
+            dropped: 0,
+            page: RefCell::new((0, vec![]))
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.spilled.len() + self.memory.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of lines of `level`, on disk and in memory, counting repeats
+    pub fn count(&self, level: LogLevel) -> usize {
+        self.counts[level_index(level)]
+    }
+
+    /// Records ever dropped from the front
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    pub fn info(&self, position: usize) -> RecordInfo {
+        match position.checked_sub(self.spilled.len()) {
+            Some(i) => self.memory[i].0,
+            None => self.spilled[position].info
+        }
+    }
+
+    /// Record at `position`, read back from disk if it was spilled
+    pub fn get(&self, position: usize) -> LogRecord {
+        if let Some(i) = position.checked_sub(self.spilled.len()) {
+            return self.memory[i].1.clone()
+        }
+
+        let mut page = self.page.borrow_mut();
+        if position < page.0 || position >= page.0 + page.1.len() {
+            let start = position - position % PAGE_SIZE;
+            let end = (start + PAGE_SIZE).min(self.spilled.len());
+            *page = (start, self.read(start, end));
+        }
+
+        page.1[position - page.0].clone()
+    }
+
+    /// Adds a record, spilling the oldest ones to disk once memory is full. A line identical
+    /// to the last one only bumps its count. Returns the records dropped from the front, as
+    /// positions move back by that many.
+    pub fn push(&mut self, record: LogRecord) -> Vec<RecordInfo> {
+        self.counts[level_index(record.level)] += record.count;
+        if let Some((info, last)) = self.memory.back_mut() {
+            if last.level == record.level && last.message == record.message {
+                last.repeat(&record);
+                info.count = last.count;
+                return vec![]
+            }
+        }
+
+        self.memory.push_back((RecordInfo::new(&record), record));
+        if self.memory.len() <= self.config.capacity {
+            return vec![]
+        }
+
+        // Spill a tenth of memory at a time so the file is opened once per batch
+        let batch = (self.config.capacity / 10).max(1).min(self.memory.len());
+        let records = self.memory.drain(..batch).collect::<Vec<(RecordInfo, LogRecord)>>();
+        if !self.disabled && self.spill(&records).is_ok() {
+            return self.rotate()
+        }
+
+        // Nowhere to put them, so the oldest records are lost. Anything already on disk is
+        // older still and goes first.
+        self.disabled = true;
+        let mut dropped = self.spilled.drain(..).map(|s| s.info).collect::<Vec<RecordInfo>>();
+        dropped.extend(records.iter().map(|(info, _)| *info));
+        self.forget(&dropped);
+        dropped
+    }
+
+    /// Deletes the spill files of this run once its logs are no longer needed. Spilled
+    /// records are dropped and anything pushed afterwards stays in memory.
+    pub fn remove_files(&mut self) -> Vec<RecordInfo> {
+        if self.generation > 0 || self.written > 0 {
+            let oldest = (self.generation + 1).saturating_sub(self.config.max_files.max(1) as u64);
+            for generation in oldest..=self.generation {
+                let _ = fs::remove_file(self.path(generation));
+            }
+        }
+
+        self.disabled = true;
+        let dropped = self.spilled.drain(..).map(|s| s.info).collect::<Vec<RecordInfo>>();
+        self.forget(&dropped);
+        dropped
+    }
+
+    /// Path of the spill file of a generation, e.g. `lean_tui_logs.3.log`
+    fn path(&self, generation: u64) -> PathBuf {
+        PathBuf::from(format!("{}.{}.log", self.config.spill_path, generation))
+    }
+
+    /// Appends records to the current file, starting a new one once it's full
+    fn spill(&mut self, records: &[(RecordInfo, LogRecord)]) -> io::Result<()> {
+        if self.written >= self.config.max_file_bytes {
+            self.generation += 1;
+            self.written = 0;
+        }
+
+        // A new generation truncates whatever an earlier run left under the same name
+        let file = OpenOptions::new()
+            .create(true)
+            .append(self.written > 0)
+            .write(true)
+            .truncate(self.written == 0)
+            .open(self.path(self.generation))?;
+
+        let mut writer = BufWriter::new(file);
+        for (info, record) in records {
+            let line = encode(record);
+            writer.write_all(line.as_bytes())?;
+            self.spilled.push_back(Spilled { file: self.generation, offset: self.written, info: *info });
+            self.written += line.len() as u64;
+        }
+
+        writer.flush()
+    }
+
+    /// Deletes files beyond `max_files`, returning the records they held
+    fn rotate(&mut self) -> Vec<RecordInfo> {
+        let oldest_kept = (self.generation + 1).saturating_sub(self.config.max_files.max(1) as u64);
+        let mut dropped = vec![];
+        while self.spilled.front().is_some_and(|s| s.file < oldest_kept) {
+            let file = self.spilled.front().unwrap().file;
+            while self.spilled.front().is_some_and(|s| s.file == file) {
+                dropped.push(self.spilled.pop_front().unwrap().info);
+            }
+            let _ = fs::remove_file(self.path(file));
+        }
+
+        self.forget(&dropped);
+        dropped
+    }
+
+    fn forget(&mut self, dropped: &[RecordInfo]) {
+        for info in dropped {
+            self.counts[level_index(info.level)] -= info.count;
+        }
+        if !dropped.is_empty() {
+            self.dropped += dropped.len();
+            *self.page.borrow_mut() = (0, vec![]);
+        }
+    }
+
+    /// Reads the spilled records in `start..end`. Records that can't be read are replaced
+    /// by a note saying so, keeping positions intact.
+    fn read(&self, start: usize, end: usize) -> Vec<LogRecord> {
+        let mut reader: Option<(u64, BufReader<File>)> = None;
+        let mut records = Vec::with_capacity(end - start);
+        for spilled in self.spilled.range(start..end) {
+            // Records of a file are contiguous, so a reader only seeks when it's opened
+            if reader.as_ref().map(|(file, _)| *file) != Some(spilled.file) {
+                reader = File::open(self.path(spilled.file))
+                    .and_then(|mut file| file.seek(SeekFrom::Start(spilled.offset)).map(|_| file))
+                    .ok()
+                    .map(|file| (spilled.file, BufReader::new(file)));
+            }
+
+            let mut line = String::new();
+            let read = match &mut reader {
+                Some((_, reader)) => reader.read_line(&mut line).unwrap_or(0) > 0,
+                None => false
+            };
+            let record = if read { decode(&line, spilled.info.level) } else { None };
+
+            records.push(record.unwrap_or_else(|| LogRecord {
+                level: spilled.info.level,
+                count: spilled.info.count,
+                message: format!("<unable to read log line from {}>", self.path(spilled.file).display()),
+                ..LogRecord::default()
+            }));
+        }
+
+        records
+    }
+}
+
+fn level_index(level: LogLevel) -> usize {
+    LogLevel::ALL.iter().position(|l| *l == level).unwrap()
+}
+
+/// Message with every run of digits replaced by `#`
+fn template(message: &str) -> String {
+    let mut template = String::with_capacity(message.len());
+    for c in message.chars() {
+        if !c.is_ascii_digit() {
+            template.push(c);
+        } else if !template.ends_with('#') {
+            template.push('#');
+        }
+    }
+
+    template
+}
+
+/// Writes a record as one tab-separated line: level, count, highlight rule, then the first
+/// and last time received and algorithm time, and the message
+fn encode(record: &LogRecord) -> String {
+    format!(
+        "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+        record.level.name(),
+        record.count,
+        record.highlight.map(|rule| rule.to_string()).unwrap_or_default(),
+        record.received.to_rfc3339(),
+        record.time(),
+        record.last_received.to_rfc3339(),
+        format_time(record.last_algorithm_time),
+        record.message
+    )
+}
+
+fn decode(line: &str, level: LogLevel) -> Option<LogRecord> {
+    let mut fields = line.trim_end_matches('\n').splitn(8, '\t').skip(1);
+    let count = fields.next()?.parse().ok()?;
+    let highlight = fields.next()?.parse().ok();
+    let received = DateTime::parse_from_rfc3339(fields.next()?).ok()?.into();
+    let algorithm_time = parse_time(fields.next()?);
+    let last_received = DateTime::parse_from_rfc3339(fields.next()?).ok()?.into();
+    let last_algorithm_time = parse_time(fields.next()?);
+
+    Some(LogRecord {
+        algorithm_time,
+        received,
+        last_algorithm_time,
+        last_received,
+        count,
+        level,
+        message: fields.next()?.to_string(),
+        highlight
+    })
+}
+
+fn format_time(time: Option<NaiveDateTime>) -> String {
+    time.map(|time| time.format(ALGORITHM_TIME_FORMAT).to_string()).unwrap_or_default()
+}
+
+fn parse_time(time: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(time, ALGORITHM_TIME_FORMAT).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn record(line: &str, level: LogLevel) -> LogRecord {
+        LogRecord::parse(line, level, Utc.with_ymd_and_hms(2021, 3, 4, 5, 6, 7).unwrap() + chrono::Duration::nanoseconds(123_456_789))
+    }
+
+    fn assert_round_trip(record: &LogRecord) {
+        let line = encode(record);
+        assert!(line.ends_with('\n') && line.matches('\n').count() == 1);
+
+        let decoded = decode(&line, record.level).unwrap();
+        assert_eq!(decoded.algorithm_time, record.algorithm_time);
+        assert_eq!(decoded.received, record.received);
+        assert_eq!(decoded.last_algorithm_time, record.last_algorithm_time);
+        assert_eq!(decoded.last_received, record.last_received);
+        assert_eq!(decoded.count, record.count);
+        assert_eq!(decoded.level, record.level);
+        assert_eq!(decoded.message, record.message);
+        assert_eq!(decoded.highlight, record.highlight);
+    }
+
+    #[test]
+    fn round_trip_with_tabs_in_message() {
+        let record = record("2020-01-02 09:31:00 : SPY\t100\t\tfilled", LogLevel::Log);
+        assert_eq!(record.message, "SPY\t100\t\tfilled");
+        assert_round_trip(&record);
+    }
+
+    #[test]
+    fn round_trip_without_algorithm_time() {
+        let record = record("   at QuantConnect.Algorithm.OnData ()", LogLevel::Error);
+        assert_eq!(record.algorithm_time, None);
+        assert_round_trip(&record);
+    }
+
+    #[test]
+    fn round_trip_with_repeats_and_highlight() {
+        let mut first = record("2020-01-02 09:31:00 : Insufficient buying power", LogLevel::Debug);
+        let mut repeat = record("2020-01-03 10:00:00 : Insufficient buying power", LogLevel::Debug);
+        repeat.received += chrono::Duration::seconds(5);
+        first.repeat(&repeat);
+        first.highlight = Some(3);
+
+        assert_eq!(first.count, 2);
+        assert_round_trip(&first);
+    }
+
+    #[test]
+    fn decode_rejects_truncated_lines() {
+        let line = encode(&record("2020-01-02 09:31:00 : hello", LogLevel::Trace));
+        assert!(decode(&line[..line.len() / 2], LogLevel::Trace).is_none());
+        assert!(decode("", LogLevel::Trace).is_none());
+    }
+
+    #[test]
+    fn spilled_records_read_back() {
+        let spill_path = std::env::temp_dir().join(format!("lean_tui_log_store_test_{}", std::process::id()));
+        let config = LogConfig {
+            capacity: 10,
+            spill_path: spill_path.display().to_string(),
+            max_file_bytes: 1 << 20,
+            max_files: 2
+        };
+        let mut store = LogStore::new(&config);
+        for i in 0..25 {
+            store.push(record(&format!("2020-01-02 09:31:00 : line\t{}", i), LogLevel::Log));
+        }
+
+        assert_eq!(store.len(), 25);
+        assert!(!store.spilled.is_empty());
+        for i in 0..25 {
+            assert_eq!(store.get(i).message, format!("line\t{}", i));
+        }
+        assert_eq!(store.count(LogLevel::Log), 25);
+
+        let path = store.path(0);
+        assert!(path.exists());
+        let dropped = store.remove_files();
+        assert!(!path.exists());
+        assert_eq!(store.len(), 25 - dropped.len());
+        assert_eq!(store.count(LogLevel::Log), store.len());
+
+        // Once the files are gone nothing is written again
+        for i in 0..25 {
+            store.push(record(&format!("2020-01-02 09:31:00 : more {}", i), LogLevel::Log));
+        }
+        assert!(!path.exists());
+    }
+}
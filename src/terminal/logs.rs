@@ -0,0 +1,450 @@
+use std::{cell::{Cell, RefCell}, rc::Rc};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use crossterm::event::KeyCode;
+
+use super::log_store::{LogStore, RecordInfo};
+
+/// Format of the algorithm time Lean puts in front of every log line
+pub const ALGORITHM_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Separator between the algorithm time and the message
+const TIME_SEPARATOR: &str = " : ";
+
+/// Severity of a log line, named after the Lean method that wrote it
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum LogLevel {
+    #[default]
+    Trace,
+    Debug,
+    Log,
+    Error
+}
+
+impl LogLevel {
+    pub const ALL: [LogLevel; 4] = [LogLevel::Trace, LogLevel::Debug, LogLevel::Log, LogLevel::Error];
+
+    /// Key that shows or hides the level in the log panel
+    pub fn toggle_key(&self) -> char {
+        match self {
+            LogLevel::Trace => 'T',
+            LogLevel::Debug => 'D',
+            LogLevel::Log => 'L',
+            LogLevel::Error => 'E'
+        }
+    }
+
+    pub fn from_toggle_key(key: char) -> Option<Self> {
+        Self::ALL.iter().copied().find(|level| level.toggle_key() == key)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LogLevel::Trace => "trace",
+            LogLevel::Debug => "debug",
+            LogLevel::Log => "log",
+            LogLevel::Error => "error"
+        }
+    }
+}
+
+/// A line of the algorithm's log, standing for `count` identical lines in a row
+#[derive(Clone, Debug, Default)]
+pub struct LogRecord {
+    /// Time in the algorithm when the line was written, if Lean prefixed it
+    pub algorithm_time: Option<NaiveDateTime>,
+    /// Wall-clock time the line reached us
+    pub received: DateTime<Utc>,
+    /// Algorithm time of the last repeat
+    pub last_algorithm_time: Option<NaiveDateTime>,
+    /// Wall-clock time of the last repeat
+    pub last_received: DateTime<Utc>,
+    pub count: usize,
+    pub level: LogLevel,
+    pub message: String,
+    /// Index of the highlight rule styling the line
+    pub highlight: Option<usize>,
+}
+
+impl LogRecord {
+    /// Splits the `2020-01-02 09:31:00 : message` prefix off a line. Lines without one, such
+    /// as stack trace lines following an error, keep the whole text as their message.
+    pub fn parse(line: &str, level: LogLevel, received: DateTime<Utc>) -> Self {
+        let parsed = line
+            .split_once(TIME_SEPARATOR)
+            .and_then(|(time, message)| Some((NaiveDateTime::parse_from_str(time.trim(), ALGORITHM_TIME_FORMAT).ok()?, message)));
+
+        let (algorithm_time, message) = match parsed {
+            Some((time, message)) => (Some(time), message),
+            None => (None, line)
+        };
+
+        Self {
+            algorithm_time,
+            received,
+            last_algorithm_time: algorithm_time,
+            last_received: received,
+            count: 1,
+            level,
+            message: message.to_string(),
+            highlight: None
+        }
+    }
+
+    /// Counts `record`, a repeat of this line, moving the last times up to it
+    pub fn repeat(&mut self, record: &LogRecord) {
+        self.count += record.count;
+        self.last_algorithm_time = record.last_algorithm_time;
+        self.last_received = record.last_received;
+    }
+
+    /// Algorithm time as Lean writes it, or an empty string
+    pub fn time(&self) -> String {
+        format_time(self.algorithm_time)
+    }
+
+    /// Algorithm time of the last repeat, or an empty string
+    pub fn last_time(&self) -> String {
+        format_time(self.last_algorithm_time)
+    }
+}
+
+fn format_time(time: Option<NaiveDateTime>) -> String {
+    time.map(|time| time.format(ALGORITHM_TIME_FORMAT).to_string()).unwrap_or_default()
+}
+
+/// Line of the log panel: a record, or a run of records with the same template when
+/// repeats are collapsed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LogLine {
+    /// Store position of the first record
+    pub first: usize,
+    /// Store position of the last record
+    pub last: usize,
+    /// Number of lines logged, counting repeats
+    pub count: usize,
+}
+
+/// Scroll position and search state of the log panel
+#[derive(Clone, Debug)]
+pub struct LogView {
+    /// Whether the newest lines are kept in view as they arrive
+    pub follow: bool,
+    /// First visible line while not following. It's an absolute index, so new lines don't
+    /// move the view.
+    pub top: usize,
+    /// Search being typed after `/`, `None` outside of search mode
+    pub input: Option<String>,
+    /// Last confirmed or typed search, matched ignoring ASCII case
+    pub query: String,
+    /// Line of the selected match
+    pub current: Option<usize>,
+    /// Number of lines the panel showed last frame, set while rendering
+    pub height: Cell<usize>,
+    /// Levels filtered out of the panel
+    pub hidden: Vec<LogLevel>,
+    /// Whether runs of lines that only differ in their numbers are shown as one
+    pub collapse: bool,
+    /// Matches counted for the title, so records on disk are only searched once per query
+    match_count: RefCell<MatchCount>,
+    /// Lines built so far, so each frame only looks at the records added since the last
+    lines: RefCell<LineCache>,
+}
+
+/// Lines out of the first `records` records, valid while the filter, collapsing and
+/// dropped records are unchanged
+#[derive(Clone, Debug, Default)]
+struct LineCache {
+    hidden: Vec<LogLevel>,
+    collapse: bool,
+    dropped: usize,
+    records: usize,
+    /// Count of the newest of those records, which grows as repeats are merged into it
+    last_count: usize,
+    /// Template of the newest shown record, which the next one collapses into if equal
+    template: Option<u64>,
+    lines: Rc<Vec<LogLine>>,
+}
+
+/// Number of matches among the first `records` lines, valid while the query, filter and
+/// dropped records are unchanged
+#[derive(Clone, Debug, Default, PartialEq)]
+struct MatchCount {
+    query: String,
+    hidden: Vec<LogLevel>,
+    collapse: bool,
+    dropped: usize,
+    records: usize,
+    matches: usize,
+}
+
+impl Default for LogView {
+    fn default() -> Self {
+        Self {
+            follow: true,
+            top: 0,
+            input: None,
+            query: String::new(),
+            current: None,
+            height: Cell::new(1),
+            hidden: vec![],
+            collapse: true,
+            match_count: RefCell::default(),
+            lines: RefCell::default()
+        }
+    }
+}
+
+impl LogView {
+    pub fn shows(&self, level: LogLevel) -> bool {
+        !self.hidden.contains(&level)
+    }
+
+    /// Lines of the panel, out of the records of the levels that aren't hidden. Scroll
+    /// positions and matches index into this. Only records added since the last call are
+    /// looked at, unless the filter, collapsing or the front of the store changed. The lines
+    /// are shared with the cache, which copies them if they're still held on the next call.
+    pub fn lines(&self, logs: &LogStore) -> Rc<Vec<LogLine>> {
+        let mut cache = self.lines.borrow_mut();
+        if cache.hidden != self.hidden || cache.collapse != self.collapse || cache.dropped != logs.dropped() || cache.records > logs.len() {
+            *cache = LineCache {
+                hidden: self.hidden.clone(),
+                collapse: self.collapse,
+                dropped: logs.dropped(),
+                ..LineCache::default()
+            };
+        }
+
+        // Repeats are merged into the newest record rather than added after it
+        let cache = &mut *cache;
+        if let Some(newest) = cache.records.checked_sub(1) {
+            let info = logs.info(newest);
+            if info.count != cache.last_count && self.shows(info.level) {
+                Rc::make_mut(&mut cache.lines).last_mut().unwrap().count += info.count - cache.last_count;
+            }
+            cache.last_count = info.count;
+        }
+
+        if cache.records < logs.len() {
+            let lines = Rc::make_mut(&mut cache.lines);
+            for i in cache.records..logs.len() {
+                let info = logs.info(i);
+                cache.last_count = info.count;
+                if !self.shows(info.level) {
+                    continue
+                }
+
+                match lines.last_mut() {
+                    Some(line) if self.collapse && cache.template == Some(info.template) => {
+                        line.last = i;
+                        line.count += info.count;
+                    },
+                    _ => lines.push(LogLine { first: i, last: i, count: info.count })
+                }
+                cache.template = Some(info.template);
+            }
+            cache.records = logs.len();
+        }
+
+        cache.lines.clone()
+    }
+
+    /// Number of lines matching the search. Only lines added since the last call are
+    /// searched, unless the search, the filter or the front of the store changed.
+    pub fn match_count(&self, logs: &LogStore, lines: &[LogLine]) -> usize {
+        let mut count = self.match_count.borrow_mut();
+        if count.query != self.query || count.hidden != self.hidden || count.collapse != self.collapse || count.dropped != logs.dropped() || count.records > lines.len() {
+            *count = MatchCount {
+                query: self.query.clone(),
+                hidden: self.hidden.clone(),
+                collapse: self.collapse,
+                dropped: logs.dropped(),
+                ..MatchCount::default()
+            };
+        }
+
+        count.matches += lines[count.records..].iter().filter(|line| self.matches(&logs.get(line.first).message)).count();
+        count.records = lines.len();
+        count.matches
+    }
+
+    /// Stops following new lines, keeping the last of `len` lines at the bottom
+    pub fn pause(&mut self, len: usize) {
+        if self.follow {
+            self.top = self.first_visible(len);
+            self.follow = false;
+        }
+    }
+
+    /// Moves the view back by the lines of records dropped from the front of the store, so
+    /// it stays where it was
+    pub fn shift(&mut self, dropped: &[RecordInfo]) {
+        let mut lines = 0;
+        let mut template = None;
+        for info in dropped.iter().filter(|info| self.shows(info.level)) {
+            if !self.collapse || template != Some(info.template) {
+                lines += 1;
+            }
+            template = Some(info.template);
+        }
+
+        self.top = self.top.saturating_sub(lines);
+        self.current = self.current.and_then(|line| line.checked_sub(lines));
+    }
+
+    /// First line to show out of `len`
+    pub fn first_visible(&self, len: usize) -> usize {
+        let height = self.height.get();
+        if self.follow {
+            len.saturating_sub(height)
+        } else {
+            self.top.min(len.saturating_sub(height))
+        }
+    }
+
+    /// Handles a log key, returning false if the key isn't one of ours. While a search is
+    /// being typed every key belongs to it.
+    pub fn key(&mut self, code: KeyCode, store: &LogStore) -> bool {
+        let lines = self.lines(store);
+        if let Some(input) = &mut self.input {
+            match code {
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => { input.pop(); },
+                KeyCode::Enter => self.input = None,
+                KeyCode::Esc => {
+                    self.input = None;
+                    self.query.clear();
+                    self.current = None;
+                    return true
+                },
+                _ => return true
+            }
+
+            if let Some(input) = &self.input {
+                self.query = input.clone();
+                let from = self.first_visible(lines.len());
+                self.jump(lines.len(), self.find(store, &lines, from, true));
+            }
+            return true
+        }
+
+        let height = self.height.get();
+        match code {
+            KeyCode::PageUp => self.scroll_to(self.first_visible(lines.len()).saturating_sub(height), lines.len()),
+            KeyCode::PageDown => self.scroll_to(self.first_visible(lines.len()) + height, lines.len()),
+            KeyCode::Up => self.scroll_to(self.first_visible(lines.len()).saturating_sub(1), lines.len()),
+            KeyCode::Down => self.scroll_to(self.first_visible(lines.len()) + 1, lines.len()),
+            KeyCode::Home => self.scroll_to(0, lines.len()),
+            KeyCode::End => self.follow = true,
+            KeyCode::Char('f') => {
+                self.top = self.first_visible(lines.len());
+                self.follow = !self.follow;
+            },
+            KeyCode::Char('/') => self.input = Some(String::new()),
+            KeyCode::Char('c') => {
+                // Line numbers change, so the selected match would point elsewhere
+                self.collapse = !self.collapse;
+                self.current = None;
+            },
+            KeyCode::Char(c) if LogLevel::from_toggle_key(c).is_some() => self.toggle(LogLevel::from_toggle_key(c).unwrap()),
+            KeyCode::Char('n') if !self.query.is_empty() => {
+                let from = self.current.map(|i| i + 1).unwrap_or_else(|| self.first_visible(lines.len()));
+                self.jump(lines.len(), self.find(store, &lines, from, true));
+            },
+            KeyCode::Char('N') if !self.query.is_empty() => {
+                let from = self.current.unwrap_or_else(|| self.first_visible(lines.len()) + height);
+                self.jump(lines.len(), self.find(store, &lines, from, false));
+            },
+            _ => return false
+        }
+
+        true
+    }
+
+    /// Shows or hides a level. Match positions refer to the filtered records, so the
+    /// selected match is dropped.
+    fn toggle(&mut self, level: LogLevel) {
+        match self.hidden.iter().position(|&l| l == level) {
+            Some(i) => { self.hidden.remove(i); },
+            None => self.hidden.push(level)
+        }
+        self.current = None;
+    }
+
+    /// Whether `line` contains the search
+    pub fn matches(&self, line: &str) -> bool {
+        !self.query.is_empty() && line.to_ascii_lowercase().contains(&self.query.to_ascii_lowercase())
+    }
+
+    /// Scrolls so `top` is the first visible line, following again once the end is reached
+    fn scroll_to(&mut self, top: usize, len: usize) {
+        let last_page = len.saturating_sub(self.height.get());
+        self.follow = top >= last_page;
+        self.top = top.min(last_page);
+    }
+
+    /// Nearest match at or after `from` going forward, or before it going backward,
+    /// wrapping around the ends. Spilled records are paged in from disk as the search
+    /// reaches them.
+    fn find(&self, store: &LogStore, lines: &[LogLine], from: usize, forward: bool) -> Option<usize> {
+        let len = lines.len();
+        if len == 0 {
+            return None
+        }
+
+        let from = from.min(len);
+        let mut order: Box<dyn Iterator<Item = usize>> = if forward {
+            Box::new((from..len).chain(0..from))
+        } else {
+            Box::new((0..from).rev().chain((from..len).rev()))
+        };
+
+        order.find(|&i| self.matches(&store.get(lines[i].first).message))
+    }
+
+    /// Selects a match and centers the view on it
+    fn jump(&mut self, len: usize, line: Option<usize>) {
+        self.current = line;
+        if let Some(line) = line {
+            self.scroll_to(line.saturating_sub(self.height.get() / 2), len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LogConfig;
+
+    #[test]
+    fn cached_lines_match_a_rebuild() {
+        let mut store = LogStore::new(&LogConfig::default());
+        let mut view = LogView::default();
+        let messages = [
+            ("order 1 filled", LogLevel::Log),
+            ("order 2 filled", LogLevel::Log),
+            ("order 2 filled", LogLevel::Log),
+            ("debugging", LogLevel::Debug),
+            ("order 3 filled", LogLevel::Log),
+            ("order 3 filled", LogLevel::Log),
+            ("boom", LogLevel::Error),
+            ("boom", LogLevel::Error),
+        ];
+
+        for (i, &(message, level)) in messages.iter().enumerate() {
+            store.push(LogRecord::parse(message, level, Utc::now()));
+            if i == 5 {
+                view.key(KeyCode::Char('c'), &store);
+            }
+            if i == 6 {
+                view.toggle(LogLevel::Debug);
+            }
+
+            let fresh = LogView { collapse: view.collapse, hidden: view.hidden.clone(), ..LogView::default() };
+            assert_eq!(*view.lines(&store), *fresh.lines(&store), "after message {}", i);
+        }
+
+        let lines = view.lines(&store);
+        assert_eq!(lines.iter().map(|line| line.count).sum::<usize>(), 7);
+        assert_eq!(lines.last().unwrap().count, 2);
+    }
+}
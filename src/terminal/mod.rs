@@ -1 +1,10 @@
-pub mod terminal_handler;
\ No newline at end of file
+pub mod chart;
+pub mod clipboard;
+pub mod errors;
+pub mod log_store;
+pub mod logs;
+pub mod pages;
+pub mod panels;
+pub mod rules;
+pub mod terminal_handler;
+pub mod theme;
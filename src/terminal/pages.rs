@@ -0,0 +1,680 @@
+use std::io::Stdout;
+use tui::{backend::CrosstermBackend, layout::{Constraint, Direction, Layout, Rect}, style::{Modifier, Style}, text::Span, widgets::{Block, Borders, Cell, List, ListItem, Row, Table}};
+
+use crate::{analytics, format, model::{Order, OrderStatus, OrderType, direction_to_span}, portfolio::{Trade, TradeStats}};
+use super::{chart::format_timestamp, panels, terminal_handler::{TerminalData, TerminalRenderer}};
+
+/// Full-screen pages selectable from the tab bar
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Page {
+    #[default]
+    Overview,
+    Orders,
+    Trades,
+    Logs,
+    Charts,
+    Stats,
+    Portfolio,
+    Errors
+}
+
+impl Page {
+    /// Pages in the order they appear in the tab bar
+    pub const ALL: [Page; 8] = [Page::Overview, Page::Orders, Page::Trades, Page::Logs, Page::Charts, Page::Stats, Page::Portfolio, Page::Errors];
+
+    pub fn title(&self) -> String {
+        let name = match self {
+            Page::Overview => "Overview",
+            Page::Orders => "Orders",
+            Page::Trades => "Trades",
+            Page::Logs => "Logs",
+            Page::Charts => "Charts",
+            Page::Stats => "Stats",
+            Page::Portfolio => "Portfolio",
+            Page::Errors => "Errors"
+        };
+
+        format!("{} {}", self.index() + 1, name)
+    }
+
+    pub fn index(&self) -> usize {
+        Self::ALL.iter().position(|p| p == self).unwrap()
+    }
+
+    /// Maps the number keys 1-8 to their page
+    pub fn from_number(number: u32) -> Option<Self> {
+        (number as usize).checked_sub(1).and_then(|i| Self::ALL.get(i).copied())
+    }
+
+    /// Finds the tab under `column`, mirroring the spacing `tui::widgets::Tabs` uses
+    /// (one space of padding on both sides of a title and a one-column divider)
+    pub fn at(column: u16, tabs_area: Rect) -> Option<Self> {
+        let mut x = tabs_area.left();
+        for page in Self::ALL.iter() {
+            let start = x + 1;
+            let end = start + page.title().len() as u16;
+            if column >= start && column < end {
+                return Some(*page)
+            }
+
+            x = end + 2;
+        }
+
+        None
+    }
+}
+
+/// Panels of the overview page, which can be focused and maximized
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Panel {
+    #[default]
+    Graph,
+    Drawdown,
+    Logs,
+    Orders,
+    Performance,
+    Metrics
+}
+
+impl Panel {
+    /// Focus order when cycling with Tab
+    pub const ALL: [Panel; 6] = [Panel::Graph, Panel::Drawdown, Panel::Logs, Panel::Orders, Panel::Performance, Panel::Metrics];
+
+    pub fn next(&self) -> Self {
+        let i = Self::ALL.iter().position(|p| p == self).unwrap();
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    pub fn previous(&self) -> Self {
+        let i = Self::ALL.iter().position(|p| p == self).unwrap();
+        Self::ALL[(i + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+}
+
+/// Everything at a glance: equity, logs, orders and statistics
+pub struct OverviewPage<'a> {
+    area: Rect,
+    left: LeftTerminalChunks<'a>,
+    right: RightTerminalChunk<'a>,
+    data: &'a TerminalData
+}
+
+pub struct LeftTerminalChunks<'a> {
+    graph: Rect,
+    drawdown: Rect,
+    logs: Rect,
+    data: &'a TerminalData
+}
+
+pub struct RightTerminalChunk<'a> {
+    orders: OrdersChunk,
+    performance: Rect,
+    metrics: Rect,
+    data: &'a TerminalData
+}
+
+pub struct OrdersChunk {
+    order_time: Rect,
+    order_type: Rect,
+    order_direction: Rect,
+    order_symbol: Rect,
+    order_quantity: Rect
+}
+
+/// Every order Lean has sent, with all of its fields
+pub struct OrdersPage<'a> {
+    area: Rect,
+    data: &'a TerminalData
+}
+
+/// Closed round trips with their statistics, above the fills they were built from
+pub struct TradesPage<'a> {
+    area: Rect,
+    data: &'a TerminalData
+}
+
+/// Positions rebuilt from the fills and what each symbol contributed
+pub struct PortfolioPage<'a> {
+    area: Rect,
+    data: &'a TerminalData
+}
+
+pub struct LogsPage<'a> {
+    area: Rect,
+    data: &'a TerminalData
+}
+
+/// Errors with their stack traces, collapsed to one line each until expanded
+pub struct ErrorsPage<'a> {
+    area: Rect,
+    data: &'a TerminalData
+}
+
+pub struct ChartsPage<'a> {
+    area: Rect,
+    data: &'a TerminalData
+}
+
+/// Lean's end-of-run and runtime statistics next to the monthly returns and their distribution
+pub struct StatsPage<'a> {
+    area: Rect,
+    data: &'a TerminalData
+}
+
+impl<'a> OverviewPage<'a> {
+    pub fn new(area: Rect, data: &'a TerminalData) -> Self {
+        let hchunk = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(75),
+                Constraint::Percentage(25)
+            ].as_ref())
+            .split(area);
+
+        let left = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Percentage(50),
+                Constraint::Percentage(20),
+                Constraint::Percentage(30)
+            ].as_ref())
+            .split(hchunk[0]);
+
+        let right = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Percentage(35),
+                Constraint::Percentage(35),
+                Constraint::Percentage(30)
+            ].as_ref())
+            .split(hchunk[1]);
+
+        let orders_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Length(27),
+                Constraint::Length(8),
+                Constraint::Length(11),
+                Constraint::Length(8),
+                Constraint::Length(11)
+            ].as_ref())
+            .split(right[0]);
+
+        Self {
+            area,
+            left: LeftTerminalChunks::new(left, data),
+            right: RightTerminalChunk::new(right, orders_chunks, data),
+            data
+        }
+    }
+
+    /// Gives the focused panel the whole area
+    fn render_zoomed(&self, frame: &mut tui::Frame<CrosstermBackend<Stdout>>) {
+        match self.data.focus {
+            Panel::Graph => panels::render_graph(frame, self.area, self.data),
+            Panel::Drawdown => panels::render_drawdown(frame, self.area, self.data),
+            Panel::Logs => panels::render_logs(frame, self.area, self.data),
+            Panel::Orders => OrdersPage::new(self.area, self.data).render(frame),
+            Panel::Performance => panels::render_statistics(frame, self.area, "Performance", Panel::Performance, self.data),
+            Panel::Metrics => panels::render_statistics(frame, self.area, "Metrics", Panel::Metrics, self.data),
+        }
+    }
+}
+
+impl<'a> LeftTerminalChunks<'a> {
+    pub fn new(chunks: Vec<Rect>, data: &'a TerminalData) -> Self {
+        Self {
+            graph: chunks[0],
+            drawdown: chunks[1],
+            logs: chunks[2],
+            data
+        }
+    }
+}
+
+impl<'a> RightTerminalChunk<'a> {
+    pub fn new(chunks: Vec<Rect>, order_chunk: Vec<Rect>, data: &'a TerminalData) -> Self {
+        Self {
+            orders: OrdersChunk::new(order_chunk),
+            performance: chunks[1],
+            metrics: chunks[2],
+            data
+        }
+    }
+
+    fn render_orders(&self, frame: &mut tui::Frame<CrosstermBackend<Stdout>>) {
+        let theme = &self.data.theme;
+        let visible = self.orders.order_time.height.saturating_sub(2) as usize;
+        let orders = &self.data.orders[self.data.orders.len().saturating_sub(visible)..];
+        let spans = orders.iter().map(|o| o.into_spans(theme)).collect::<Vec<_>>();
+
+        let columns: [(&str, Rect, Vec<ListItem>); 5] = [
+            ("Time", self.orders.order_time, spans.iter().map(|s| ListItem::new(s.0.clone())).collect()),
+            ("Type", self.orders.order_type, spans.iter().map(|s| ListItem::new(s.1.clone())).collect()),
+            ("Direction", self.orders.order_direction, spans.iter().map(|s| ListItem::new(s.2.clone())).collect()),
+            ("Symbol", self.orders.order_symbol, spans.iter().map(|s| ListItem::new(s.4.clone())).collect()),
+            ("Quantity", self.orders.order_quantity, spans.iter().map(|s| ListItem::new(s.3.clone())).collect()),
+        ];
+
+        for (title, area, items) in columns.iter() {
+            let block = Block::default()
+                .title(*title)
+                .borders(Borders::ALL)
+                .border_style(self.data.border_style(Panel::Orders));
+
+            frame.render_widget(List::new(items.clone()).block(block), *area);
+        }
+    }
+}
+
+impl OrdersChunk {
+    pub fn new(chunk: Vec<Rect>) -> Self {
+        Self {
+            order_time: chunk[0],
+            order_type: chunk[1],
+            order_direction: chunk[2],
+            order_symbol: chunk[3],
+            order_quantity: chunk[4]
+        }
+    }
+}
+
+impl<'a> TerminalRenderer for OverviewPage<'a> {
+    fn render(&self, frame: &mut tui::Frame<CrosstermBackend<Stdout>>) {
+        if self.data.zoomed {
+            self.render_zoomed(frame);
+            return
+        }
+
+        self.left.render(frame);
+        self.right.render(frame);
+    }
+}
+
+impl<'a> TerminalRenderer for LeftTerminalChunks<'a> {
+    fn render(&self, frame: &mut tui::Frame<CrosstermBackend<Stdout>>) {
+        panels::render_logs(frame, self.logs, self.data);
+        panels::render_graph(frame, self.graph, self.data);
+        panels::render_drawdown(frame, self.drawdown, self.data);
+    }
+}
+
+impl<'a> TerminalRenderer for RightTerminalChunk<'a> {
+    fn render(&self, frame: &mut tui::Frame<CrosstermBackend<Stdout>>) {
+        self.render_orders(frame);
+        panels::render_statistics(frame, self.performance, "Performance", Panel::Performance, self.data);
+        panels::render_statistics(frame, self.metrics, "Metrics", Panel::Metrics, self.data);
+    }
+}
+
+impl<'a> OrdersPage<'a> {
+    pub fn new(area: Rect, data: &'a TerminalData) -> Self {
+        Self { area, data }
+    }
+}
+
+impl<'a> TradesPage<'a> {
+    pub fn new(area: Rect, data: &'a TerminalData) -> Self {
+        Self { area, data }
+    }
+}
+
+impl<'a> PortfolioPage<'a> {
+    pub fn new(area: Rect, data: &'a TerminalData) -> Self {
+        Self { area, data }
+    }
+}
+
+impl<'a> LogsPage<'a> {
+    pub fn new(area: Rect, data: &'a TerminalData) -> Self {
+        Self { area, data }
+    }
+}
+
+impl<'a> ErrorsPage<'a> {
+    pub fn new(area: Rect, data: &'a TerminalData) -> Self {
+        Self { area, data }
+    }
+}
+
+impl<'a> ChartsPage<'a> {
+    pub fn new(area: Rect, data: &'a TerminalData) -> Self {
+        Self { area, data }
+    }
+}
+
+impl<'a> StatsPage<'a> {
+    pub fn new(area: Rect, data: &'a TerminalData) -> Self {
+        Self { area, data }
+    }
+}
+
+/// Shows the newest rows that fit below the header of a bordered table
+fn last_rows<T>(rows: &[T], area: Rect) -> &[T] {
+    let visible = area.height.saturating_sub(3) as usize;
+    &rows[rows.len().saturating_sub(visible)..]
+}
+
+fn header_row<'a>(titles: &[&'a str]) -> Row<'a> {
+    Row::new(titles.to_vec())
+        .style(Style::default().add_modifier(Modifier::BOLD))
+}
+
+impl<'a> TerminalRenderer for OrdersPage<'a> {
+    fn render(&self, frame: &mut tui::Frame<CrosstermBackend<Stdout>>) {
+        let theme = &self.data.theme;
+        let block = Block::default()
+            .title(format!("Orders ({})", self.data.orders.len()))
+            .borders(Borders::ALL)
+            .border_style(self.data.border_style(Panel::Orders));
+
+        // The newest orders, or those around the selection once one was picked
+        let orders = &self.data.orders;
+        let visible = self.area.height.saturating_sub(3) as usize;
+        let selected = self.data.order_selected.unwrap_or_else(|| orders.len().saturating_sub(1));
+        let end = (selected + 1).max(visible).min(orders.len());
+        let start = end.saturating_sub(visible);
+
+        let rows = orders[start..end]
+            .iter()
+            .enumerate()
+            .map(|(i, order)| Row::new(vec![
+                Cell::from(order.Id.to_string()),
+                Cell::from(order.Time.clone()),
+                Cell::from(order.Symbol.Value.clone()),
+                Cell::from(OrderType::from(order.Type).to_string()),
+                Cell::from(direction_to_span(order.Direction, theme)),
+                Cell::from(order.Quantity.to_string()),
+                Cell::from(order.Price.to_string()),
+                Cell::from(OrderStatus::from(order.Status).to_string()),
+                Cell::from(order.Value.to_string()),
+                Cell::from(order.Tag.clone().unwrap_or_default()),
+            ]).style(if start + i == selected { theme.highlight() } else { Style::default() }))
+            .collect::<Vec<Row>>();
+
+        let widths = [
+            Constraint::Length(6),
+            Constraint::Length(21),
+            Constraint::Length(10),
+            Constraint::Length(7),
+            Constraint::Length(9),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(15),
+            Constraint::Length(12),
+            Constraint::Min(0),
+        ];
+
+        let table = Table::new(rows)
+            .header(header_row(&["Id", "Time", "Symbol", "Type", "Direction", "Quantity", "Price", "Status", "Value", "Tag"]))
+            .block(block)
+            .widths(&widths);
+
+        frame.render_widget(table, self.area);
+    }
+}
+
+impl<'a> TerminalRenderer for TradesPage<'a> {
+    fn render(&self, frame: &mut tui::Frame<CrosstermBackend<Stdout>>) {
+        let trades = &self.data.trades;
+        let mut symbols = trades.iter().map(|t| t.symbol.as_str()).collect::<Vec<&str>>();
+        symbols.sort_unstable();
+        symbols.dedup();
+
+        // A row per symbol and one for all of them, plus the header and borders
+        let summary_height = (symbols.len() as u16 + 4).min(self.area.height * 2 / 5);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(summary_height),
+                Constraint::Min(0)
+            ].as_ref())
+            .split(self.area);
+
+        let lists = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Percentage(60),
+                Constraint::Percentage(40)
+            ].as_ref())
+            .split(chunks[1]);
+
+        self.render_summary(frame, chunks[0], trades, &symbols);
+        self.render_closed(frame, lists[0], trades);
+        self.render_fills(frame, lists[1]);
+    }
+}
+
+impl<'a> TradesPage<'a> {
+    /// Trade statistics of every symbol, since Lean only reports them for the whole portfolio
+    fn render_summary(&self, frame: &mut tui::Frame<CrosstermBackend<Stdout>>, area: Rect, trades: &[Trade], symbols: &[&str]) {
+        let currency = self.data.currency_symbol();
+        let theme = &self.data.theme;
+        let block = Block::default()
+            .title(format!("Closed Trades by Symbol │ {} matching", self.data.trade_matching.name()))
+            .borders(Borders::ALL)
+            .border_style(theme.border());
+
+        let row = |name: &str, stats: TradeStats| Row::new(vec![
+            Cell::from(name.to_string()),
+            Cell::from(stats.count.to_string()),
+            Cell::from(format!("{:.1}%", stats.win_rate)),
+            Cell::from(stats.profit_factor.map(|f| format!("{:.2}", f)).unwrap_or_else(|| String::from("-"))),
+            Cell::from(format::currency(stats.average_win, &currency)).style(theme.pnl(stats.average_win)),
+            Cell::from(format::currency(stats.average_loss, &currency)).style(theme.pnl(stats.average_loss)),
+            Cell::from(format::currency(stats.expectancy, &currency)).style(theme.pnl(stats.expectancy)),
+            Cell::from(stats.longest_win_streak.to_string()),
+            Cell::from(stats.longest_loss_streak.to_string()),
+        ]);
+
+        let mut rows = symbols
+            .iter()
+            .filter_map(|&symbol| TradeStats::new(trades.iter().filter(|t| t.symbol == symbol)).map(|stats| row(symbol, stats)))
+            .collect::<Vec<Row>>();
+
+        if let Some(stats) = TradeStats::new(trades.iter()) {
+            rows.push(row("All", stats).style(Style::default().add_modifier(Modifier::BOLD)));
+        }
+
+        let widths = [
+            Constraint::Length(10),
+            Constraint::Length(7),
+            Constraint::Length(9),
+            Constraint::Length(14),
+            Constraint::Length(14),
+            Constraint::Length(14),
+            Constraint::Length(14),
+            Constraint::Length(11),
+            Constraint::Min(0),
+        ];
+
+        let table = Table::new(rows)
+            .header(header_row(&["Symbol", "Trades", "Win Rate", "Profit Factor", "Avg Win", "Avg Loss", "Expectancy", "Win Streak", "Loss Streak"]))
+            .block(block)
+            .widths(&widths);
+
+        frame.render_widget(table, area);
+    }
+
+    /// Entries paired with the exits that closed them
+    fn render_closed(&self, frame: &mut tui::Frame<CrosstermBackend<Stdout>>, area: Rect, trades: &[Trade]) {
+        let currency = self.data.currency_symbol();
+        let theme = &self.data.theme;
+        let time = |x: f64| if x > 0.0 { format_timestamp(x, "%Y-%m-%d %H:%M") } else { String::new() };
+        let block = Block::default()
+            .title(format!("Closed Trades ({})", trades.len()))
+            .borders(Borders::ALL)
+            .border_style(theme.border());
+
+        let rows = last_rows(trades, area)
+            .iter()
+            .map(|trade| Row::new(vec![
+                Cell::from(trade.symbol.clone()),
+                Cell::from(if trade.quantity < 0.0 {
+                    Span::styled("Short", Style::default().fg(theme.sell))
+                } else {
+                    Span::styled("Long", Style::default().fg(theme.buy))
+                }),
+                Cell::from(time(trade.entry_time)),
+                Cell::from(time(trade.exit_time)),
+                Cell::from(trade.quantity.abs().to_string()),
+                Cell::from(format::currency(trade.entry_price, &currency)),
+                Cell::from(format::currency(trade.exit_price, &currency)),
+                Cell::from(format::currency(trade.pnl, &currency)).style(theme.pnl(trade.pnl)),
+                Cell::from(format!("{:.2}%", trade.return_pct())).style(theme.pnl(trade.pnl)),
+                Cell::from(format::duration(trade.duration())),
+            ]))
+            .collect::<Vec<Row>>();
+
+        let widths = [
+            Constraint::Length(10),
+            Constraint::Length(9),
+            Constraint::Length(16),
+            Constraint::Length(16),
+            Constraint::Length(10),
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Length(14),
+            Constraint::Length(9),
+            Constraint::Min(0),
+        ];
+
+        let table = Table::new(rows)
+            .header(header_row(&["Symbol", "Side", "Entry", "Exit", "Quantity", "Entry Price", "Exit Price", "P&L", "Return", "Held"]))
+            .block(block)
+            .widths(&widths);
+
+        frame.render_widget(table, area);
+    }
+
+    /// Filled orders as they were executed
+    fn render_fills(&self, frame: &mut tui::Frame<CrosstermBackend<Stdout>>, area: Rect) {
+        let theme = &self.data.theme;
+        let fills = self.data.orders
+            .iter()
+            .filter(|o| matches!(OrderStatus::from(o.Status), OrderStatus::Filled | OrderStatus::PartiallyFilled))
+            .collect::<Vec<&Order>>();
+
+        let block = Block::default()
+            .title(format!("Fills ({})", fills.len()))
+            .borders(Borders::ALL)
+            .border_style(theme.border());
+
+        let rows = last_rows(&fills, area)
+            .iter()
+            .map(|order| Row::new(vec![
+                Cell::from(order.LastFillTime.clone().unwrap_or_else(|| order.Time.clone())),
+                Cell::from(order.Symbol.Value.clone()),
+                Cell::from(direction_to_span(order.Direction, theme)),
+                Cell::from(order.Quantity.to_string()),
+                Cell::from(order.Price.to_string()),
+                Cell::from(Span::styled(order.Value.to_string(), theme.text())),
+            ]))
+            .collect::<Vec<Row>>();
+
+        let widths = [
+            Constraint::Length(21),
+            Constraint::Length(10),
+            Constraint::Length(9),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Min(0),
+        ];
+
+        let table = Table::new(rows)
+            .header(header_row(&["Fill Time", "Symbol", "Direction", "Quantity", "Fill Price", "Value"]))
+            .block(block)
+            .widths(&widths);
+
+        frame.render_widget(table, area);
+    }
+}
+
+impl<'a> TerminalRenderer for PortfolioPage<'a> {
+    fn render(&self, frame: &mut tui::Frame<CrosstermBackend<Stdout>>) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Percentage(50),
+                Constraint::Percentage(50)
+            ].as_ref())
+            .split(self.area);
+
+        panels::render_holdings(frame, chunks[0], self.data);
+        panels::render_attribution(frame, chunks[1], self.data);
+    }
+}
+
+impl<'a> TerminalRenderer for LogsPage<'a> {
+    fn render(&self, frame: &mut tui::Frame<CrosstermBackend<Stdout>>) {
+        panels::render_logs(frame, self.area, self.data);
+    }
+}
+
+impl<'a> TerminalRenderer for ErrorsPage<'a> {
+    fn render(&self, frame: &mut tui::Frame<CrosstermBackend<Stdout>>) {
+        panels::render_errors(frame, self.area, self.data);
+    }
+}
+
+impl<'a> TerminalRenderer for ChartsPage<'a> {
+    fn render(&self, frame: &mut tui::Frame<CrosstermBackend<Stdout>>) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Percentage(50),
+                Constraint::Percentage(20),
+                Constraint::Percentage(30)
+            ].as_ref())
+            .split(self.area);
+
+        panels::render_graph(frame, chunks[0], self.data);
+        panels::render_drawdown(frame, chunks[1], self.data);
+        let bottom = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(50),
+                Constraint::Percentage(50)
+            ].as_ref())
+            .split(chunks[2]);
+
+        panels::render_rolling(frame, bottom[0], self.data);
+        panels::render_asset(frame, bottom[1], self.data);
+    }
+}
+
+impl<'a> TerminalRenderer for StatsPage<'a> {
+    fn render(&self, frame: &mut tui::Frame<CrosstermBackend<Stdout>>) {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(40),
+                Constraint::Percentage(60)
+            ].as_ref())
+            .split(self.area);
+
+        let tables = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Percentage(60),
+                Constraint::Percentage(40)
+            ].as_ref())
+            .split(columns[0]);
+
+        panels::render_statistics(frame, tables[0], "Statistics", Panel::Metrics, self.data);
+        panels::render_statistics(frame, tables[1], "Runtime Statistics", Panel::Performance, self.data);
+
+        // Header, borders and a row per year, leaving the histogram at least half of the column
+        let years = analytics::monthly_returns(&self.data.equity).len() as u16;
+        let charts = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length((years + 3).min(columns[1].height / 2)),
+                Constraint::Min(0)
+            ].as_ref())
+            .split(columns[1]);
+
+        panels::render_monthly_returns(frame, charts[0], self.data);
+        panels::render_histogram(frame, charts[1], self.data);
+    }
+}
@@ -0,0 +1,810 @@
+use std::io::Stdout;
+use tui::{backend::CrosstermBackend, layout::{Constraint, Rect}, style::{Modifier, Style}, symbols::Marker, text::{Span, Spans}, widgets::{Axis, BarChart, Block, Borders, Cell, Chart, Dataset, GraphType, List, ListItem, Paragraph, Row, Table}};
+
+use crate::{analytics, format, portfolio};
+use super::{chart::{format_timestamp, label_count, plot_area, ticks, time_labels, value_labels}, logs::{LogLevel, LogRecord}, pages::Panel, terminal_handler::TerminalData};
+
+/// Renders the logs at the panel's scroll position, highlighting search matches. Repeated
+/// lines show their count and when the last one was logged. The title shows the visible
+/// range and the search being typed.
+pub fn render_logs(frame: &mut tui::Frame<CrosstermBackend<Stdout>>, area: Rect, data: &TerminalData) {
+    let view = &data.log_view;
+    view.height.set(area.height.saturating_sub(2).max(1) as usize);
+
+    let lines = view.lines(&data.logs);
+    let first = view.first_visible(lines.len());
+    let shown = lines[first..(first + view.height.get()).min(lines.len())]
+        .iter()
+        .map(|line| {
+            let record = data.logs.get(line.first);
+            let last_time = if line.last == line.first { record.last_time() } else { data.logs.get(line.last).last_time() };
+            (line.count, record, last_time)
+        })
+        .collect::<Vec<(usize, LogRecord, String)>>();
+
+    let mut title = String::from("Algorithm Logs");
+    if !lines.is_empty() {
+        title.push_str(&format!(" │ {}-{} of {}", first + 1, first + shown.len(), lines.len()));
+    }
+    if !view.follow {
+        title.push_str(" │ paused");
+    }
+    if !view.collapse {
+        title.push_str(" │ repeats expanded");
+    }
+    match &view.input {
+        Some(input) => title.push_str(&format!(" │ /{}_", input)),
+        None if !view.query.is_empty() => {
+            let matches = view.match_count(&data.logs, &lines);
+            title.push_str(&format!(" │ /{} ({} matches)", view.query, matches));
+        },
+        None => {}
+    }
+
+    // Per level counts, with hidden levels struck through
+    let mut title = vec![Span::raw(title)];
+    for level in LogLevel::ALL.iter() {
+        let count = data.logs.count(*level);
+        let style = if view.shows(*level) {
+            data.theme.text()
+        } else {
+            data.theme.dimmed().add_modifier(Modifier::CROSSED_OUT)
+        };
+
+        title.push(Span::raw(" │ "));
+        title.push(Span::styled(format!("{} {}", level.name(), count), style));
+    }
+
+    let log_block = Block::default()
+        .title(Spans::from(title))
+        .borders(Borders::ALL)
+        .border_style(data.border_style(Panel::Logs));
+
+    let logs = shown
+        .iter()
+        .enumerate()
+        .map(|(i, (count, record, last_time))| {
+            let style = match record.highlight.and_then(|rule| data.log_rules.style(rule)) {
+                Some(style) => style,
+                None if record.level == LogLevel::Error => data.theme.error(),
+                None => data.theme.text()
+            };
+            let match_style = if view.current == Some(first + i) {
+                data.theme.highlight()
+            } else {
+                style.fg(data.theme.highlight).add_modifier(Modifier::BOLD)
+            };
+
+            let mut spans = vec![Span::styled(format!("{:<19} ", record.time()), data.theme.dimmed())];
+            spans.extend(highlight_matches(&record.message, &view.query, style, match_style));
+            if *count > 1 {
+                spans.push(Span::styled(format!(" ×{}", count), data.theme.text().add_modifier(Modifier::BOLD)));
+                if *last_time != record.time() {
+                    spans.push(Span::styled(format!(" last {}", last_time), data.theme.dimmed()));
+                }
+            }
+            ListItem::new(Spans::from(spans))
+        })
+        .collect::<Vec<ListItem>>();
+
+    let log_widget = List::new(logs)
+        .block(log_block);
+
+    frame.render_widget(log_widget, area);
+}
+
+/// Splits `text` into spans, styling every occurrence of `query` (ignoring ASCII case)
+/// with `match_style`
+fn highlight_matches<'a>(text: &'a str, query: &str, style: Style, match_style: Style) -> Vec<Span<'a>> {
+    if query.is_empty() {
+        return vec![Span::styled(text, style)]
+    }
+
+    // ASCII lowercasing keeps byte offsets, so positions in `lower` are valid in `text`
+    let lower = text.to_ascii_lowercase();
+    let query = query.to_ascii_lowercase();
+    let mut spans = vec![];
+    let mut last = 0;
+    for (start, _) in lower.match_indices(&query) {
+        if start < last {
+            continue
+        }
+        if start > last {
+            spans.push(Span::styled(&text[last..start], style));
+        }
+        spans.push(Span::styled(&text[start..start + query.len()], match_style));
+        last = start + query.len();
+    }
+    if last < text.len() {
+        spans.push(Span::styled(&text[last..], style));
+    }
+
+    spans
+}
+
+/// Renders one line per error, with the stack traces of expanded entries indented below
+/// them. The view scrolls so the selected entry and as much of its trace as fits are shown.
+pub fn render_errors(frame: &mut tui::Frame<CrosstermBackend<Stdout>>, area: Rect, data: &TerminalData) {
+    let view = &data.error_view;
+    let mut lines = vec![];
+    let mut selected = (0, 0);
+    for (i, entry) in data.errors.iter().enumerate() {
+        let expanded = view.is_expanded(i);
+        let marker = if entry.trace.is_empty() { " " } else if expanded { "▾" } else { "▸" };
+        let mut headline = vec![
+            Span::raw(format!("{} ", marker)),
+            Span::styled(format!("{:<19} ", entry.time()), data.theme.dimmed())
+        ];
+        if let Some(name) = entry.exception_name() {
+            headline.push(Span::styled(format!("{}: ", name), data.theme.error()));
+        }
+        headline.push(Span::styled(entry.message.clone(), data.theme.text()));
+        if !expanded && !entry.trace.is_empty() {
+            headline.push(Span::styled(format!(" ({} more lines)", entry.trace.len()), data.theme.dimmed()));
+        }
+
+        let start = lines.len();
+        let style = if i == view.selected { data.theme.highlight() } else { Style::default() };
+        lines.push(ListItem::new(Spans::from(headline)).style(style));
+        if expanded {
+            lines.extend(entry.trace.iter().map(|line| ListItem::new(Span::styled(format!("    {}", line), data.theme.dimmed()))));
+        }
+        if i == view.selected {
+            selected = (start, lines.len());
+        }
+    }
+
+    let height = area.height.saturating_sub(2) as usize;
+    let first = selected.1.saturating_sub(height).min(selected.0);
+    let shown = lines.into_iter().skip(first).take(height).collect::<Vec<ListItem>>();
+
+    let title = if data.errors.is_empty() {
+        String::from("Errors")
+    } else {
+        format!("Errors │ {} of {} │ Enter to expand", view.selected.min(data.errors.len() - 1) + 1, data.errors.len())
+    };
+
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(data.theme.border());
+
+    frame.render_widget(List::new(shown).block(block), area);
+}
+
+/// Renders the strategy equity curve inside the window of `data.equity_view`, with the
+/// benchmark rebased to the starting equity, the crosshair and any in-progress mouse
+/// selection drawn on top
+pub fn render_graph(frame: &mut tui::Frame<CrosstermBackend<Stdout>>, area: Rect, data: &TerminalData) {
+    let view = &data.equity_view;
+    let mut title = String::from("Backtest Performance");
+    let graph_block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(data.border_style(Panel::Graph));
+
+    let inner = graph_block.inner(area);
+    let (equity, hidden) = view.transform(view.visible(&data.equity));
+    if equity.is_empty() {
+        view.plot.set(inner);
+        frame.render_widget(graph_block.title(title), area);
+        return
+    }
+
+    if view.log_scale {
+        title.push_str(" (log)");
+    }
+    if hidden > 0 {
+        title.push_str(&format!(" │ {} non-positive points hidden", hidden));
+    }
+
+    let benchmark = if data.show_benchmark {
+        let rebased = analytics::rebase(&data.benchmark, data.equity[0].0, data.equity[0].1);
+        view.transform(view.visible(&rebased)).0
+    } else {
+        vec![]
+    };
+
+    let (x_min, x_max) = view.bounds(&data.equity).unwrap();
+    let x_axis_bounds = [x_min, x_max];
+    let y_axis_bounds = [
+        equity.iter().chain(benchmark.iter()).map(|(_, y)| y).fold(f64::INFINITY, |a, &b| a.min(b)),
+        equity.iter().chain(benchmark.iter()).map(|(_, y)| y).fold(f64::NEG_INFINITY, |a, &b| a.max(b))
+    ];
+
+    let symbol = data.currency_symbol();
+    let x_labels = time_labels(x_min, x_max, label_count(inner.width, 10, 8))
+        .into_iter()
+        .map(|l| Span::styled(l, data.theme.text()))
+        .collect::<Vec<Span>>();
+    let y_labels = view.y_labels(y_axis_bounds[0], y_axis_bounds[1], label_count(inner.height, 0, 6), &symbol)
+        .into_iter()
+        .map(|l| Span::styled(l, data.theme.text()))
+        .collect::<Vec<Span>>();
+
+    view.plot.set(plot_area(inner, &x_labels, &y_labels));
+
+    // Traced as a single polyline: up the vertical line, back to the point, then across
+    let crosshair = view.cursor.map(|i| {
+        let (x, y) = data.equity[i];
+        title.push_str(&format!(" │ {} │ {}", format_timestamp(x, "%Y-%m-%d %H:%M"), format::currency(y, &symbol)));
+
+        let mut line = vec![(x, y_axis_bounds[0]), (x, y_axis_bounds[1])];
+        if let Some(y) = view.scale_y(y) {
+            line.extend_from_slice(&[(x, y), (x_min, y), (x_max, y)]);
+        }
+        line
+    });
+
+    let selection = view.selection
+        .filter(|(start, end)| start != end)
+        .and_then(|(start, end)| Some((view.x_at(start, &data.equity)?, view.x_at(end, &data.equity)?)))
+        .map(|(from, to)| vec![(from, y_axis_bounds[0]), (from, y_axis_bounds[1]), (to, y_axis_bounds[1]), (to, y_axis_bounds[0])]);
+
+    // Fills are placed on the equity curve at the time they happened
+    let (buys, sells) = if data.show_trades {
+        trade_markers(&data.fills, |fill| {
+            if fill.time < x_min || fill.time > x_max {
+                return None
+            }
+            analytics::value_at(&data.equity, fill.time).and_then(|y| view.scale_y(y)).map(|y| (fill.time, y))
+        })
+    } else {
+        (vec![], vec![])
+    };
+
+    // The legend goes in the title, since tui hides its own once the plot is short
+    let mut legend = vec![Span::raw(" │ "), Span::styled("• Equity", Style::default().fg(data.theme.equity))];
+    let mut datasets = vec![
+    Dataset::default()
+        .graph_type(GraphType::Line)
+        .marker(Marker::Dot)
+        .style(Style::default().fg(data.theme.equity))
+        .data(&equity)];
+
+    if !benchmark.is_empty() {
+        legend.push(Span::styled("  • Benchmark", Style::default().fg(data.theme.benchmark)));
+        datasets.push(Dataset::default()
+            .graph_type(GraphType::Line)
+            // A different marker keeps the two lines apart when colors are disabled
+            .marker(Marker::Braille)
+            .style(Style::default().fg(data.theme.benchmark))
+            .data(&benchmark));
+    }
+
+    for (name, color, markers) in [("Buys", data.theme.buy, &buys), ("Sells", data.theme.sell, &sells)].iter() {
+        if !markers.is_empty() {
+            legend.push(Span::styled(format!("  █ {}", name), Style::default().fg(*color)));
+            datasets.push(Dataset::default()
+                .graph_type(GraphType::Scatter)
+                .marker(Marker::Block)
+                .style(Style::default().fg(*color))
+                .data(markers));
+        }
+    }
+
+    if let Some(crosshair) = &crosshair {
+        datasets.push(Dataset::default()
+            .graph_type(GraphType::Line)
+            .marker(Marker::Braille)
+            .style(Style::default().fg(data.theme.highlight))
+            .data(crosshair));
+    }
+
+    if let Some(selection) = &selection {
+        datasets.push(Dataset::default()
+            .graph_type(GraphType::Line)
+            .marker(Marker::Braille)
+            .style(Style::default().fg(data.theme.highlight))
+            .data(selection));
+    }
+
+    let mut title = vec![Span::raw(title)];
+    title.extend(legend);
+
+    let graph_widget = Chart::new(datasets)
+    .block(graph_block.title(Spans::from(title)))
+    .x_axis(Axis::default()
+        .title("Time")
+        .bounds(x_axis_bounds)
+        .labels(x_labels))
+    .y_axis(Axis::default()
+        .title("Equity")
+        .bounds(y_axis_bounds)
+        .labels(y_labels));
+
+    frame.render_widget(graph_widget, area);
+}
+
+/// Renders the underwater curve below the equity chart, following its visible window and
+/// crosshair. The deepest and current drawdowns are summarized in the title.
+pub fn render_drawdown(frame: &mut tui::Frame<CrosstermBackend<Stdout>>, area: Rect, data: &TerminalData) {
+    let view = &data.equity_view;
+    let drawdown = data.drawdown();
+    let mut title = String::from("Drawdown");
+
+    if let Some(stats) = analytics::DrawdownStats::new(&drawdown) {
+        let recovery = match stats.recovery {
+            Some(x) => format!("recovered {}", format_timestamp(x, "%Y-%m-%d")),
+            None => String::from("not recovered")
+        };
+
+        title.push_str(&format!(" │ Max {:.2}% {} → {}, {} │ Current {:.2}%",
+            stats.max,
+            format_timestamp(stats.start, "%Y-%m-%d"),
+            format_timestamp(stats.trough, "%Y-%m-%d"),
+            recovery,
+            stats.current));
+    }
+
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(data.border_style(Panel::Drawdown));
+
+    let inner = block.inner(area);
+    let visible = view.visible(&drawdown);
+    let (x_min, x_max) = match view.bounds(&data.equity).or_else(|| view.bounds(&drawdown)) {
+        Some(bounds) if !visible.is_empty() => bounds,
+        _ => {
+            frame.render_widget(block, area);
+            return
+        }
+    };
+
+    // Keep a sliver of range so a flat line at 0% still has a y axis
+    let y_min = visible.iter().map(|(_, y)| *y).fold(-0.01, f64::min);
+    let y_labels = ticks(y_min, 0.0, label_count(inner.height, 0, 4))
+        .into_iter()
+        .map(|y| Span::styled(format!("{:.1}%", y), data.theme.text()))
+        .collect::<Vec<Span>>();
+
+    let crosshair = view.cursor
+        .map(|i| data.equity[i].0)
+        .map(|x| vec![(x, y_min), (x, 0.0)]);
+
+    let mut datasets = vec![
+    Dataset::default()
+        .graph_type(GraphType::Line)
+        .marker(Marker::Braille)
+        .style(Style::default().fg(data.theme.drawdown))
+        .data(visible)];
+
+    if let Some(crosshair) = &crosshair {
+        datasets.push(Dataset::default()
+            .graph_type(GraphType::Line)
+            .marker(Marker::Braille)
+            .style(Style::default().fg(data.theme.highlight))
+            .data(crosshair));
+    }
+
+    let chart = Chart::new(datasets)
+        .block(block)
+        .x_axis(Axis::default()
+            .bounds([x_min, x_max]))
+        .y_axis(Axis::default()
+            .bounds([y_min, 0.0])
+            .labels(y_labels));
+
+    frame.render_widget(chart, area);
+}
+
+/// Chart points of buy fills and of sell fills
+type TradeMarkers = (Vec<(f64, f64)>, Vec<(f64, f64)>);
+
+/// Splits fills into buy and sell chart points, skipping those `point` can't place
+fn trade_markers(fills: &[portfolio::Fill], point: impl Fn(&portfolio::Fill) -> Option<(f64, f64)>) -> TradeMarkers {
+    let mut buys = vec![];
+    let mut sells = vec![];
+    for fill in fills.iter().filter(|f| f.time > 0.0) {
+        if let Some(xy) = point(fill) {
+            if fill.quantity > 0.0 { buys.push(xy) } else { sells.push(xy) }
+        }
+    }
+
+    (buys, sells)
+}
+
+/// Renders the price of one symbol from Lean's "Assets" chart with its fills at the fill
+/// price, over the equity chart's window
+pub fn render_asset(frame: &mut tui::Frame<CrosstermBackend<Stdout>>, area: Rect, data: &TerminalData) {
+    let view = &data.equity_view;
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(data.theme.border());
+
+    let symbols = data.asset_symbols();
+    let name = match symbols.get(data.asset % symbols.len().max(1)) {
+        Some(name) => name,
+        None => {
+            frame.render_widget(block.title("Assets"), area);
+            return
+        }
+    };
+
+    let no_prices = vec![];
+    let prices = data.assets.get(name).unwrap_or(&no_prices);
+    let fills = data.fills
+        .iter()
+        .filter(|f| &f.symbol == name)
+        .cloned()
+        .collect::<Vec<portfolio::Fill>>();
+
+    let title = format!("{} │ {} of {} │ {} fills", name, data.asset % symbols.len() + 1, symbols.len(), fills.len());
+    let (x_min, x_max) = match view.bounds(&data.equity).or_else(|| view.bounds(prices)) {
+        Some(bounds) => bounds,
+        None => {
+            frame.render_widget(block.title(title), area);
+            return
+        }
+    };
+
+    let visible = view.visible(prices);
+    let (buys, sells) = trade_markers(&fills, |fill| Some((fill.time, fill.price)).filter(|(x, _)| *x >= x_min && *x <= x_max));
+    let values = visible.iter().chain(buys.iter()).chain(sells.iter()).map(|(_, y)| *y);
+    let y_min = values.clone().fold(f64::INFINITY, f64::min);
+    let y_max = values.fold(f64::NEG_INFINITY, f64::max);
+    if !y_min.is_finite() {
+        frame.render_widget(block.title(title), area);
+        return
+    }
+    let (y_min, y_max) = if y_max > y_min { (y_min, y_max) } else { (y_min - 0.5, y_max + 0.5) };
+
+    let inner = block.inner(area);
+    let y_labels = value_labels(&ticks(y_min, y_max, label_count(inner.height, 0, 4)), &data.currency_symbol())
+        .into_iter()
+        .map(|l| Span::styled(l, data.theme.text()))
+        .collect::<Vec<Span>>();
+
+    let mut datasets = vec![
+    Dataset::default()
+        .graph_type(GraphType::Line)
+        .marker(Marker::Braille)
+        .style(Style::default().fg(data.theme.equity))
+        .data(visible)];
+
+    for (color, markers) in [(data.theme.buy, &buys), (data.theme.sell, &sells)].iter() {
+        datasets.push(Dataset::default()
+            .graph_type(GraphType::Scatter)
+            .marker(Marker::Block)
+            .style(Style::default().fg(*color))
+            .data(markers));
+    }
+
+    let chart = Chart::new(datasets)
+        .block(block.title(title))
+        .x_axis(Axis::default()
+            .bounds([x_min, x_max]))
+        .y_axis(Axis::default()
+            .bounds([y_min, y_max])
+            .labels(y_labels));
+
+    frame.render_widget(chart, area);
+}
+
+/// Renders the selected rolling risk metric over the equity chart's window
+pub fn render_rolling(frame: &mut tui::Frame<CrosstermBackend<Stdout>>, area: Rect, data: &TerminalData) {
+    let view = &data.equity_view;
+    let metric = data.rolling.metric;
+    let series = analytics::rolling(&data.equity, &data.benchmark, data.rolling.window, metric);
+    let format = |value: f64| match metric {
+        analytics::RiskMetric::Volatility => format!("{:.1}%", value),
+        _ => format!("{:.2}", value)
+    };
+
+    let mut title = format!("Rolling {} │ {} days", metric.name(), data.rolling.window);
+    if let Some(&(_, last)) = series.last() {
+        title.push_str(&format!(" │ Current {}", format(last)));
+    }
+
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(data.theme.border());
+
+    let inner = block.inner(area);
+    let visible = view.visible(&series);
+    let (x_min, x_max) = match view.bounds(&data.equity) {
+        Some(bounds) if !visible.is_empty() => bounds,
+        _ => {
+            frame.render_widget(block, area);
+            return
+        }
+    };
+
+    let y_min = visible.iter().map(|(_, y)| *y).fold(f64::INFINITY, f64::min);
+    let y_max = visible.iter().map(|(_, y)| *y).fold(f64::NEG_INFINITY, f64::max);
+    let (y_min, y_max) = if y_max > y_min { (y_min, y_max) } else { (y_min - 0.5, y_max + 0.5) };
+    let y_labels = ticks(y_min, y_max, label_count(inner.height, 0, 4))
+        .into_iter()
+        .map(|y| Span::styled(format(y), data.theme.text()))
+        .collect::<Vec<Span>>();
+
+    let crosshair = view.cursor
+        .map(|i| data.equity[i].0)
+        .map(|x| vec![(x, y_min), (x, y_max)]);
+
+    let mut datasets = vec![
+    Dataset::default()
+        .graph_type(GraphType::Line)
+        .marker(Marker::Braille)
+        .style(Style::default().fg(data.theme.equity))
+        .data(visible)];
+
+    if let Some(crosshair) = &crosshair {
+        datasets.push(Dataset::default()
+            .graph_type(GraphType::Line)
+            .marker(Marker::Braille)
+            .style(Style::default().fg(data.theme.highlight))
+            .data(crosshair));
+    }
+
+    let chart = Chart::new(datasets)
+        .block(block)
+        .x_axis(Axis::default()
+            .bounds([x_min, x_max]))
+        .y_axis(Axis::default()
+            .bounds([y_min, y_max])
+            .labels(y_labels));
+
+    frame.render_widget(chart, area);
+}
+
+/// Renders a histogram of period returns, marking the bucket holding the mean. The bucket
+/// width is doubled until every bucket fits in the panel.
+pub fn render_histogram(frame: &mut tui::Frame<CrosstermBackend<Stdout>>, area: Rect, data: &TerminalData) {
+    let view = &data.histogram;
+    let returns = analytics::returns(&analytics::resample(&data.equity, view.period))
+        .into_iter()
+        .map(|(_, r)| r)
+        .collect::<Vec<f64>>();
+
+    let mut title = format!("{} Returns", view.period.name());
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(data.theme.border());
+    let inner = block.inner(area);
+
+    let moments = match analytics::Moments::new(&returns) {
+        Some(moments) if inner.width > 0 && inner.height > 0 => moments,
+        _ => {
+            frame.render_widget(block.title(title), area);
+            return
+        }
+    };
+
+    let max_buckets = (inner.width as usize / 2).max(1);
+    let mut width = view.bucket_width;
+    let mut buckets = analytics::histogram(&returns, width);
+    while buckets.len() > max_buckets {
+        width *= 2.0;
+        buckets = analytics::histogram(&returns, width);
+    }
+
+    title.push_str(&format!(" │ bucket {:.2}% │ mean {:.3}% │ skew {:.2} │ kurtosis {:.2} │ n={}",
+        width, moments.mean, moments.skew, moments.kurtosis, moments.count));
+
+    let gap = 1;
+    let bar_width = (inner.width / buckets.len() as u16).saturating_sub(gap).max(1);
+    let labels = buckets
+        .iter()
+        .map(|(lower, _)| format!("{:.1}", lower))
+        .map(|label| if label.len() as u16 <= bar_width { label } else { String::new() })
+        .collect::<Vec<String>>();
+    let bars = labels
+        .iter()
+        .zip(buckets.iter())
+        .map(|(label, (_, count))| (label.as_str(), *count))
+        .collect::<Vec<(&str, u64)>>();
+
+    let chart = BarChart::default()
+        .block(block.title(title))
+        .data(&bars)
+        .bar_width(bar_width)
+        .bar_gap(gap)
+        .bar_style(Style::default().fg(data.theme.equity))
+        .value_style(data.theme.highlight())
+        .label_style(data.theme.text());
+
+    frame.render_widget(chart, area);
+
+    // BarChart can't style a single bar, so the mean is marked above its bucket instead
+    let mean_bucket = ((moments.mean / width).floor() - (buckets[0].0 / width).round()) as u16;
+    let column = inner.x + mean_bucket * (bar_width + gap);
+    if column < inner.right() {
+        let marker = Rect::new(column, inner.y, (inner.right() - column).min(2), 1);
+        frame.render_widget(Paragraph::new(Span::styled("▼μ", Style::default().fg(data.theme.highlight))), marker);
+    }
+}
+
+/// Renders the tearsheet table of monthly returns, one row per year. Months and year totals
+/// are shaded on separate scales since a year usually moves several times more than a month.
+pub fn render_monthly_returns(frame: &mut tui::Frame<CrosstermBackend<Stdout>>, area: Rect, data: &TerminalData) {
+    let years = analytics::monthly_returns(&data.equity);
+    let month_scale = years
+        .iter()
+        .flat_map(|y| y.months.iter().flatten())
+        .fold(0.0f64, |max, r| max.max(r.abs()));
+    let year_scale = years.iter().fold(0.0f64, |max, y| max.max(y.total.abs()));
+
+    let header = Row::new(std::iter::once("Year")
+        .chain(analytics::MONTHS.iter().copied())
+        .chain(std::iter::once("Total"))
+        .collect::<Vec<&str>>())
+        .style(data.theme.highlight());
+
+    let rows = years
+        .iter()
+        .map(|y| {
+            let months = y.months.iter().map(|month| match month {
+                Some(r) => Cell::from(format!("{:.1}", r)).style(data.theme.heat(*r, month_scale)),
+                None => Cell::from("")
+            });
+
+            Row::new(std::iter::once(Cell::from(y.year.to_string()))
+                .chain(months)
+                .chain(std::iter::once(Cell::from(format!("{:.1}", y.total)).style(data.theme.heat(y.total, year_scale))))
+                .collect::<Vec<Cell>>())
+        })
+        .collect::<Vec<Row>>();
+
+    let mut widths = vec![Constraint::Length(5); analytics::MONTHS.len() + 2];
+    widths[0] = Constraint::Length(4);
+    widths[analytics::MONTHS.len() + 1] = Constraint::Length(6);
+
+    let table = Table::new(rows)
+        .header(header)
+        .block(Block::default()
+            .title("Monthly Returns %")
+            .borders(Borders::ALL)
+            .border_style(data.theme.border()))
+        .widths(&widths)
+        .column_spacing(1);
+
+    frame.render_widget(table, area);
+}
+
+/// Renders every traded symbol's position, open ones first, with profit marked at the latest
+/// "Assets" chart price
+pub fn render_holdings(frame: &mut tui::Frame<CrosstermBackend<Stdout>>, area: Rect, data: &TerminalData) {
+    let symbol = data.currency_symbol();
+    let money = |value: Option<f64>| value.map(|v| format::currency(v, &symbol)).unwrap_or_default();
+    let pnl = |value: Option<f64>| match value {
+        Some(v) => Cell::from(format::currency(v, &symbol)).style(data.theme.pnl(v)),
+        None => Cell::from("")
+    };
+
+    let mut positions = data.positions.iter().collect::<Vec<&portfolio::Position>>();
+    positions.sort_by_key(|p| !p.is_open());
+
+    let open = positions.iter().filter(|p| p.is_open()).count();
+    let realized = positions.iter().map(|p| p.realized).sum::<f64>();
+    let unrealized = positions.iter().filter_map(|p| p.unrealized()).sum::<f64>();
+    let block = Block::default()
+        .title(format!("Holdings ({} open) │ Realized {} │ Unrealized {}",
+            open,
+            format::currency(realized, &symbol),
+            format::currency(unrealized, &symbol)))
+        .borders(Borders::ALL)
+        .border_style(data.theme.border());
+
+    let rows = positions
+        .iter()
+        .map(|p| {
+            let row = Row::new(vec![
+                Cell::from(p.symbol.clone()),
+                Cell::from(p.quantity.to_string()),
+                Cell::from(if p.is_open() { money(Some(p.average_price)) } else { String::new() }),
+                Cell::from(money(p.last_fill_price)),
+                Cell::from(money(p.mark_price.filter(|_| p.is_open()))),
+                Cell::from(money(p.market_value().filter(|_| p.is_open()))),
+                pnl(p.unrealized().filter(|_| p.is_open())),
+                pnl(Some(p.realized)),
+            ]);
+
+            if p.is_open() { row } else { row.style(Style::default().add_modifier(Modifier::DIM)) }
+        })
+        .collect::<Vec<Row>>();
+
+    let widths = [
+        Constraint::Length(10),
+        Constraint::Length(12),
+        Constraint::Length(14),
+        Constraint::Length(14),
+        Constraint::Length(14),
+        Constraint::Length(16),
+        Constraint::Length(16),
+        Constraint::Min(0),
+    ];
+
+    let table = Table::new(rows)
+        .header(Row::new(vec!["Symbol", "Quantity", "Avg Price", "Last Fill", "Mark", "Market Value", "Unrealized", "Realized"])
+            .style(Style::default().add_modifier(Modifier::BOLD)))
+        .block(block)
+        .widths(&widths);
+
+    frame.render_widget(table, area);
+}
+
+/// Renders the P&L, order count, volume and fees of every symbol, sorted by the selected column
+pub fn render_attribution(frame: &mut tui::Frame<CrosstermBackend<Stdout>>, area: Rect, data: &TerminalData) {
+    let symbol = data.currency_symbol();
+    let money = |value: f64| format::currency(value, &symbol);
+    let pnl = |value: f64| Cell::from(money(value)).style(data.theme.pnl(value));
+
+    let mut rows = portfolio::attribution(&data.orders, &data.fills, &data.positions);
+    data.attribution_sort.sort(&mut rows, data.attribution_descending);
+
+    let block = Block::default()
+        .title(format!("P&L Attribution ({} symbols)", rows.len()))
+        .borders(Borders::ALL)
+        .border_style(data.theme.border());
+
+    let arrow = if data.attribution_descending { "▼" } else { "▲" };
+    let header = portfolio::AttributionColumn::ALL
+        .iter()
+        .map(|column| match column {
+            c if *c == data.attribution_sort => Span::styled(format!("{}{}", c.name(), arrow), data.theme.highlight()),
+            c => Span::raw(c.name())
+        })
+        .map(Cell::from)
+        .collect::<Vec<Cell>>();
+
+    let rows = rows
+        .iter()
+        .map(|a| Row::new(vec![
+            Cell::from(a.symbol.clone()),
+            pnl(a.realized),
+            pnl(a.unrealized),
+            pnl(a.total()),
+            Cell::from(a.orders.to_string()),
+            Cell::from(money(a.volume)),
+            Cell::from(money(a.fees)),
+        ]))
+        .collect::<Vec<Row>>();
+
+    let widths = [
+        Constraint::Length(10),
+        Constraint::Length(16),
+        Constraint::Length(16),
+        Constraint::Length(16),
+        Constraint::Length(8),
+        Constraint::Length(18),
+        Constraint::Min(0),
+    ];
+
+    let table = Table::new(rows)
+        .header(Row::new(header).style(Style::default().add_modifier(Modifier::BOLD)))
+        .block(block)
+        .widths(&widths);
+
+    frame.render_widget(table, area);
+}
+
+/// Renders a key/value statistics table as sent by Lean. `Panel::Performance` holds the
+/// runtime statistics and `Panel::Metrics` the end-of-run statistics.
+pub fn render_statistics(frame: &mut tui::Frame<CrosstermBackend<Stdout>>, area: Rect, title: &str, panel: Panel, data: &TerminalData) {
+    let block = Block::default()
+        .title(title.to_string())
+        .borders(Borders::ALL)
+        .border_style(data.border_style(panel));
+
+    let statistics = match panel {
+        Panel::Performance => &data.runtime_statistics,
+        _ => &data.statistics
+    };
+
+    let rows = statistics
+        .iter()
+        .map(|(name, value)| Row::new(vec![
+            Cell::from(name.as_str()),
+            Cell::from(value.as_str()).style(data.theme.text())
+        ]))
+        .collect::<Vec<Row>>();
+
+    let widths = [Constraint::Percentage(60), Constraint::Percentage(40)];
+    let table = Table::new(rows)
+        .block(block)
+        .widths(&widths);
+
+    frame.render_widget(table, area);
+}
@@ -0,0 +1,133 @@
+use regex::Regex;
+use serde::Deserialize;
+use tui::style::Style;
+
+use super::theme::Theme;
+
+/// What happens when a log line matches a highlight rule, besides being styled
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleAction {
+    /// Rings the terminal bell
+    Bell,
+    /// Adds the message to the error panel
+    Pin,
+    /// Stops the log panel from following new lines
+    Pause
+}
+
+/// Highlight rule as written in the config file, e.g.
+/// `{ "pattern": "MarginCall", "color": "red", "bold": true, "actions": ["bell", "pin"] }`
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct HighlightRule {
+    /// Regular expression searched for in the message, without the algorithm time
+    pub pattern: String,
+    /// Text color: a name such as `red` or `light-blue`, an index from 0 to 255 or `#rrggbb`
+    pub color: Option<String>,
+    /// Background color, in the same format as `color`
+    pub background: Option<String>,
+    pub bold: bool,
+    pub actions: Vec<RuleAction>,
+}
+
+/// A rule with its pattern compiled
+#[derive(Clone, Debug)]
+pub struct LogRule {
+    pub regex: Regex,
+    /// Style of matching lines, `None` if the rule only has actions
+    pub style: Option<Style>,
+    pub actions: Vec<RuleAction>,
+}
+
+/// Highlight rules of the config, in the order they were written
+#[derive(Clone, Debug, Default)]
+pub struct LogRules {
+    rules: Vec<LogRule>,
+}
+
+impl LogRules {
+    /// Compiles the rules, leaving out those with an invalid pattern. A message is returned
+    /// for each of them.
+    pub fn new(rules: &[HighlightRule], theme: &Theme) -> (Self, Vec<String>) {
+        let mut compiled = vec![];
+        let mut errors = vec![];
+        for rule in rules {
+            let regex = match Regex::new(&rule.pattern) {
+                Ok(regex) => regex,
+                Err(err) => {
+                    errors.push(format!("Invalid highlight rule pattern `{}`: {}", rule.pattern, err));
+                    continue
+                }
+            };
+
+            let styled = rule.color.is_some() || rule.background.is_some() || rule.bold;
+            compiled.push(LogRule {
+                regex,
+                style: if styled { Some(theme.rule(rule.color.as_deref(), rule.background.as_deref(), rule.bold)) } else { None },
+                actions: rule.actions.clone()
+            });
+        }
+
+        (Self { rules: compiled }, errors)
+    }
+
+    /// Index of the first styled rule whose pattern is found in `message`, which decides its
+    /// highlight, and the actions of every rule that matches
+    pub fn apply(&self, message: &str) -> (Option<usize>, Vec<RuleAction>) {
+        let mut highlight = None;
+        let mut actions = vec![];
+        for (i, rule) in self.rules.iter().enumerate().filter(|(_, rule)| rule.regex.is_match(message)) {
+            if highlight.is_none() && rule.style.is_some() {
+                highlight = Some(i);
+            }
+            actions.extend(rule.actions.iter().copied());
+        }
+
+        (highlight, actions)
+    }
+
+    /// Style of the rule at `index`, as stored on a record
+    pub fn style(&self, index: usize) -> Option<Style> {
+        self.rules.get(index).and_then(|rule| rule.style)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terminal::theme::ThemePreset;
+
+    fn rule(pattern: &str, color: Option<&str>, actions: Vec<RuleAction>) -> HighlightRule {
+        HighlightRule { pattern: pattern.to_string(), color: color.map(str::to_string), actions, ..HighlightRule::default() }
+    }
+
+    #[test]
+    fn invalid_patterns_are_reported() {
+        let theme = Theme::preset(ThemePreset::Dark);
+        let (rules, errors) = LogRules::new(&[rule("(unclosed", Some("red"), vec![]), rule("ok", Some("red"), vec![])], &theme);
+
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("`(unclosed`"));
+        // The valid rule is kept and indices count compiled rules only
+        assert_eq!(rules.apply("ok"), (Some(0), vec![]));
+        assert_eq!(rules.style(1), None);
+    }
+
+    #[test]
+    fn first_styled_match_wins() {
+        let theme = Theme::preset(ThemePreset::Dark);
+        let (rules, errors) = LogRules::new(&[
+            rule("Margin", None, vec![RuleAction::Bell]),
+            rule("Margin", Some("red"), vec![]),
+            rule("Call$", Some("blue"), vec![RuleAction::Pin]),
+        ], &theme);
+        assert!(errors.is_empty());
+
+        assert_eq!(rules.apply("MarginCall"), (Some(1), vec![RuleAction::Bell, RuleAction::Pin]));
+        assert_eq!(rules.apply("Call"), (Some(2), vec![RuleAction::Pin]));
+        assert_eq!(rules.apply("Calls"), (None, vec![]));
+        assert_eq!(rules.style(0), None);
+        assert_eq!(rules.style(1), Some(theme.rule(Some("red"), None, false)));
+    }
+}
@@ -1,10 +1,17 @@
+use std::{collections::{BTreeMap, HashSet}, io::{stdout, Stdout, Write}, path::PathBuf, sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}}, thread, time::{Duration, Instant}};
+use tui::{Terminal, backend::CrosstermBackend, layout::{Constraint, Direction, Layout, Rect}, style::{Modifier, Style}, text::{Span, Spans}, widgets::{Block, Borders, Paragraph, Tabs}};
+use crate::{Message, analytics, config::{Config, ReportConfig}, export::{self, ChartSeries}, format, model::{Order, OrderEvent, OrderStatus, OrderType, direction_name}, portfolio::{self, AttributionColumn, Fill, Matching, Position, Trade}, report::{Metadata, Report}};
+use super::{chart::{ChartView, HistogramView, RollingView, format_timestamp}, clipboard, errors::{self, ErrorEntry, ErrorView}, log_store::LogStore, logs::{LogLevel, LogRecord, LogView}, pages::*, rules::{LogRules, RuleAction}, theme::Theme};
+use crossterm::{event::{self, EnableMouseCapture, Event, KeyCode, KeyEvent, MouseButton, MouseEvent}, execute, terminal::{EnterAlternateScreen, enable_raw_mode}};
 
+/// Height of the tab bar, including its borders
+pub const TAB_BAR_HEIGHT: u16 = 3;
 
-use std::{io::{stdout, Stdout, Read, Write}, sync::{Arc, Mutex}, thread};
-use tui::{Terminal, backend::CrosstermBackend, layout::{Constraint, Direction, Layout}, style::{Color, Style}, symbols::Marker, text::Span, widgets::{Axis, Block, Borders, Chart, Dataset, GraphType, List, ListItem, Widget}};
-use crate::Message;
-use crossterm::{event::EnableMouseCapture, execute, terminal::{EnterAlternateScreen, enable_raw_mode}};
+/// Longest the draw thread waits for a message before redrawing, so the error badge can flash
+const FRAME_INTERVAL: Duration = Duration::from_millis(250);
 
+/// How long the error badge flashes after a new error, and notices stay in the tab bar
+const FLASH_DURATION: Duration = Duration::from_secs(3);
 
 /// In charge of handling rendering to the terminal frame
 pub trait TerminalRenderer {
@@ -22,45 +29,86 @@ pub struct TerminalHandler {
     pub receiver: crossbeam_channel::Receiver<Message>,
     /// Background thread manages and receives BacktestPackets from Lean
     pub bg_thread: Option<thread::JoinHandle<()>>,
+    /// Polls keyboard and mouse events and forwards them to the background thread
+    pub input_thread: Option<thread::JoinHandle<()>>,
+    /// Cleared when Lean frees the terminal so that the input thread stops reading stdin
+    pub running: Arc<AtomicBool>,
+    /// User configuration loaded when the terminal was created
+    pub config: Config,
+    /// Whether orders and charts are exported once Lean stops the terminal
+    pub export_on_free: Arc<AtomicBool>,
 }
 
 #[derive(Clone, Debug, Default)]
-pub struct TerminalData<'a> {
-    logs: Vec<ListItem<'a>>,
-    equity: Vec<(f64, f64)>,
-
-    order_time: Vec<Span<'a>>,
-    order_type: Vec<Span<'a>>,
-    order_sides: Vec<Span<'a>>,
-    order_qty: Vec<Span<'a>>,
-    order_symbol: Vec<Span<'a>>,
+pub struct TerminalData {
+    pub(crate) theme: Theme,
+    pub(crate) page: Page,
+    /// Overview panel receiving panel-specific keys
+    pub(crate) focus: Panel,
+    /// Whether the focused panel fills the terminal
+    pub(crate) zoomed: bool,
+
+    pub(crate) logs: LogStore,
+    pub(crate) log_view: LogView,
+    /// Highlight rules of the config
+    pub(crate) log_rules: LogRules,
+    /// Errors and runtime errors, with their stack traces grouped
+    pub(crate) errors: Vec<ErrorEntry>,
+    pub(crate) error_view: ErrorView,
+    /// When the last error arrived, to flash the error badge
+    pub(crate) last_error: Option<Instant>,
+    /// Short message shown in the tab bar, such as what was copied, and when it was set
+    pub(crate) notice: Option<(String, Instant)>,
+    pub(crate) equity: Vec<(f64, f64)>,
+    pub(crate) equity_view: ChartView,
+    pub(crate) benchmark: Vec<(f64, f64)>,
+    /// Lean's own drawdown series, only sent by recent versions
+    pub(crate) lean_drawdown: Vec<(f64, f64)>,
+    pub(crate) histogram: HistogramView,
+    pub(crate) rolling: RollingView,
+    /// Whether the benchmark is drawn over the equity curve
+    pub(crate) show_benchmark: bool,
+    /// Whether fills are marked on the equity curve
+    pub(crate) show_trades: bool,
+    /// Price series of the "Assets" chart by symbol
+    pub(crate) assets: BTreeMap<String, Vec<(f64, f64)>>,
+    /// Every series of every chart, kept for exports
+    pub(crate) charts: ChartSeries,
+    /// Directory CSV exports are written to
+    pub(crate) export_directory: PathBuf,
+    /// Format and path of the report written when the run stops
+    pub(crate) report: ReportConfig,
+    /// Details of the backtest from the latest packet, for the report
+    pub(crate) metadata: Metadata,
+    /// Index into `asset_symbols` of the symbol shown on the charts page
+    pub(crate) asset: usize,
+    pub(crate) orders: Vec<Order>,
+    /// Order selected on the orders page, `None` keeping the newest selected
+    pub(crate) order_selected: Option<usize>,
+    /// Fills of the orders, when Lean sends them
+    pub(crate) order_events: Vec<OrderEvent>,
+    /// Order and event ids of `order_events`, to skip events sent again
+    pub(crate) order_event_ids: HashSet<(u64, u64)>,
+    /// Executions, positions and closed trades rebuilt from the orders whenever a packet
+    /// arrives, so that pages don't replay the fills on every frame
+    pub(crate) fills: Vec<Fill>,
+    pub(crate) positions: Vec<Position>,
+    pub(crate) trades: Vec<Trade>,
+    pub(crate) trade_matching: Matching,
+    pub(crate) attribution_sort: AttributionColumn,
+    pub(crate) attribution_descending: bool,
+
+    /// Currency code of the algorithm's account, e.g. USD
+    pub(crate) account_currency: String,
+    pub(crate) statistics: Vec<(String, String)>,
+    pub(crate) runtime_statistics: Vec<(String, String)>,
 }
 
+/// Root renderer, draws the tab bar and the selected page below it
 pub struct Term<'a> {
-    pub left: LeftTerminalChunks<'a>,
-    pub right: RightTerminalChunk<'a>,
-    data: &'a TerminalData<'a>
-}
-
-pub struct LeftTerminalChunks<'a> {
-    graph: tui::layout::Rect,
-    logs: tui::layout::Rect,
-    data: &'a TerminalData<'a>
-}
-
-pub struct RightTerminalChunk<'a> {
-    orders: OrdersChunk,
-    performance: tui::layout::Rect,
-    metrics: tui::layout::Rect,
-    data: &'a TerminalData<'a>
-}
-
-pub struct OrdersChunk {
-    order_time: tui::layout::Rect,
-    order_type: tui::layout::Rect,
-    order_direction: tui::layout::Rect,
-    order_symbol: tui::layout::Rect,
-    order_quantity: tui::layout::Rect
+    tabs: Rect,
+    page: Box<dyn TerminalRenderer + 'a>,
+    data: &'a TerminalData
 }
 
 
@@ -74,12 +122,18 @@ impl Default for TerminalHandler {
         let backend = CrosstermBackend::new(stdout);
         let terminal = Arc::new(Mutex::new(Terminal::new(backend).expect("Error creating terminal")));
         let (tx, rx) = crossbeam_channel::unbounded();
-        
+
+        let config = Config::load();
+
         Self {
             terminal,
             tx,
             receiver: rx,
-            bg_thread: None
+            bg_thread: None,
+            input_thread: None,
+            running: Arc::new(AtomicBool::new(true)),
+            export_on_free: Arc::new(AtomicBool::new(config.export.on_free)),
+            config
         }
     }
 }
@@ -88,9 +142,11 @@ impl TerminalHandler {
     pub fn start(&mut self) {
         let terminal = self.terminal.clone();
         let rx = self.receiver.clone();
+        let config = self.config.clone();
+        let export_on_free = self.export_on_free.clone();
 
         self.bg_thread = Some(thread::spawn(move || {
-            let mut terminal_data = TerminalData::default();
+            let mut terminal_data = TerminalData::new(&config);
             let mut finished = false;
 
             while !finished {
@@ -103,7 +159,7 @@ impl TerminalHandler {
                             return
                         }
 
-                        Term::render(f, &terminal_data);
+                        Term::new(f.size(), &terminal_data).render(f);
                 })
                 .unwrap();
 
@@ -111,257 +167,533 @@ impl TerminalHandler {
                     break;
                 }
             }
+
+            // Stdout is being handed back to Lean, so failures can only go to a file
+            if export_on_free.load(Ordering::Relaxed) {
+                if let Err(err) = terminal_data.export() {
+                    std::fs::write("lean_tui_export_error.log", format!("{}: {:?}", terminal_data.export_directory.display(), err)).unwrap();
+                }
+            }
+        }));
+
+        let tx = self.tx.clone();
+        let running = self.running.clone();
+
+        self.input_thread = Some(thread::spawn(move || {
+            // Poll with a timeout so that we notice when Lean is done with the terminal
+            while running.load(Ordering::Relaxed) {
+                if let Ok(true) = event::poll(Duration::from_millis(100)) {
+                    // The draw thread may already be gone if Lean is freeing the terminal
+                    if let Ok(event) = event::read() {
+                        let _ = tx.send(Message::Input(event));
+                    }
+                }
+            }
         }));
     }
 }
 
 impl<'a> Term<'a> {
-    pub fn render(frame: &mut tui::Frame<CrosstermBackend<Stdout>>, terminal_data: &'a TerminalData) {
-        let hchunk = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([
-                Constraint::Percentage(75),
-                Constraint::Percentage(25)
-            ].as_ref())
-            .split(frame.size());
-
-        let left = Layout::default()
+    pub fn new(area: Rect, data: &'a TerminalData) -> Self {
+        // A maximized panel takes the tab bar's space as well
+        let tab_bar_height = if data.is_zoomed() { 0 } else { TAB_BAR_HEIGHT };
+        let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
-                Constraint::Percentage(70),
-                Constraint::Percentage(30)
+                Constraint::Length(tab_bar_height),
+                Constraint::Min(0)
             ].as_ref())
-            .split(hchunk[0]);
+            .split(area);
+
+        let body = chunks[1];
+        let page: Box<dyn TerminalRenderer> = match data.page {
+            Page::Overview => Box::new(OverviewPage::new(body, data)),
+            Page::Orders => Box::new(OrdersPage::new(body, data)),
+            Page::Trades => Box::new(TradesPage::new(body, data)),
+            Page::Logs => Box::new(LogsPage::new(body, data)),
+            Page::Charts => Box::new(ChartsPage::new(body, data)),
+            Page::Stats => Box::new(StatsPage::new(body, data)),
+            Page::Portfolio => Box::new(PortfolioPage::new(body, data)),
+            Page::Errors => Box::new(ErrorsPage::new(body, data)),
+        };
 
-        let right = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Percentage(35),
-                Constraint::Percentage(35),
-                Constraint::Percentage(30)
-            ].as_ref())
-            .split(hchunk[1]);
+        Self {
+            tabs: chunks[0],
+            page,
+            data
+        }
+    }
+}
 
-        let orders_chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([
-                Constraint::Length(27),
-                Constraint::Length(8),
-                Constraint::Length(11),
-                Constraint::Length(8),
-                Constraint::Length(11)
-            ].as_ref())
-            .split(right[0]);
+impl<'a> TerminalRenderer for Term<'a> {
+    fn render(&self, frame: &mut tui::Frame<CrosstermBackend<Stdout>>) {
+        self.page.render(frame);
+        if self.data.is_zoomed() {
+            return
+        }
 
-        let renderer = Self {
-            left: LeftTerminalChunks::new(left, &terminal_data),
-            right: RightTerminalChunk::new(right, orders_chunks, &terminal_data),
-            data: terminal_data
-        };
+        let theme = &self.data.theme;
+        let titles = Page::ALL
+            .iter()
+            .map(|p| Spans::from(Span::styled(p.title(), theme.text())))
+            .collect::<Vec<Spans>>();
+
+        let tabs = Tabs::new(titles)
+            .block(Block::default().borders(Borders::ALL).border_style(theme.border()))
+            .select(self.data.page.index())
+            .highlight_style(theme.highlight());
+
+        frame.render_widget(tabs, self.tabs);
 
-        renderer.left.render(frame);
-        renderer.right.render(frame);
+        // Notice and error count badge, right-aligned on the tab bar's top border
+        let mut badges = vec![];
+        if let Some((notice, _)) = self.data.notice.as_ref().filter(|(_, time)| time.elapsed() < FLASH_DURATION) {
+            badges.push(Span::styled(format!(" {} ", notice), theme.text()));
+        }
+        let errors = self.data.errors.len();
+        if errors > 0 {
+            let style = if self.data.error_flash() { theme.error().add_modifier(Modifier::REVERSED) } else { theme.error() };
+            badges.push(Span::styled(format!(" ✖ {} ", errors), style));
+        }
+
+        let width = badges.iter().map(|badge| badge.width() as u16).sum::<u16>();
+        if width > 0 && self.tabs.width.saturating_sub(2) >= width && self.tabs.height > 0 {
+            let area = Rect::new(self.tabs.right() - 1 - width, self.tabs.top(), width, 1);
+            frame.render_widget(Paragraph::new(Spans::from(badges)), area);
+        }
     }
 }
 
 
-impl<'a> TerminalData<'a> {
+impl TerminalData {
+    pub fn new(config: &Config) -> Self {
+        let theme = Theme::from_config(config);
+        let (log_rules, rule_errors) = LogRules::new(&config.highlights, &theme);
+        let mut data = Self {
+            theme,
+            log_rules,
+            account_currency: format::DEFAULT_ACCOUNT_CURRENCY.to_string(),
+            show_benchmark: true,
+            show_trades: true,
+            histogram: HistogramView::new(&config.histogram),
+            rolling: RollingView::new(&config.rolling),
+            trade_matching: config.trade_matching,
+            attribution_descending: true,
+            logs: LogStore::new(&config.logs),
+            export_directory: PathBuf::from(&config.export.directory),
+            report: config.report.clone(),
+            ..Self::default()
+        };
+
+        // Bad patterns show up in the error panel rather than failing the whole config
+        for err in rule_errors {
+            data.log(err, LogLevel::Error);
+        }
+
+        data
+    }
+
     pub fn handle_data(&mut self, rx: &crossbeam_channel::Receiver<Message>) -> bool {
-        match rx.recv() {
-            Ok(val) => {
-                match val {
-                    Message::Log(msg, error) => self.log(msg, error),
-                    Message::Packet(packet) => self.packet(packet),
-                    Message::Stop => return true
+        if let Ok(val) = rx.recv_timeout(FRAME_INTERVAL) {
+            match val {
+                Message::Log(msg, level) => self.log(msg, level),
+                Message::Packet(packet) => self.packet(*packet),
+                Message::Input(event) => self.input(event),
+                Message::Stop => {
+                    self.stop();
+                    self.logs.remove_files();
+                    return true
                 }
-            },
-            Err(_) => {}
+            }
         };
 
         false
     }
 
-    fn log(&mut self, msg: String, error: bool) {
-        let log_style = Style::default()
-            .fg(if error { Color::Red } else { Color::Reset });
+    pub fn currency_symbol(&self) -> String {
+        format::currency_symbol(&self.account_currency)
+    }
 
-        for line in msg.lines() {
-            let log_line = ListItem::new(Span::styled(line.to_string(), log_style));
-            &self.logs.push(log_line);
+    /// Whether the error badge is in the highlighted half of its flash
+    pub fn error_flash(&self) -> bool {
+        match self.last_error.map(|time| time.elapsed()) {
+            Some(elapsed) if elapsed < FLASH_DURATION => (elapsed.as_millis() / FRAME_INTERVAL.as_millis()).is_multiple_of(2),
+            _ => false
         }
     }
 
-    fn packet(&mut self, packet: crate::model::BacktestResultPacket) {
-        if packet.Results.Charts.is_some() {
-            let packet_charts = packet.Results.Charts.unwrap();
-            match packet_charts.get("Strategy Equity").map(|v| v.Series.get("Equity").unwrap()) {
-                Some(points) => {
-                    let new_points = points.Values.clone()
-                        .into_iter()
-                        .map(|xy| (xy.x, xy.y))
-                        .filter(|(_, y)| y > &0f64)
-                        .collect::<Vec<(f64, f64)>>();
-
-                    for point in new_points {
-                        &self.equity.push(point);
-                    }
+    /// Whether a maximized panel is currently covering the overview page
+    pub fn is_zoomed(&self) -> bool {
+        self.zoomed && self.page == Page::Overview
+    }
 
-                    &self.equity.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
-                    &self.equity.dedup();
-                },
-                None => {}
-            };
+    /// Border of a panel, highlighted when it has focus on the overview page
+    pub fn border_style(&self, panel: Panel) -> Style {
+        if self.page == Page::Overview && self.focus == panel {
+            Style::default().fg(self.theme.highlight)
+        } else {
+            self.theme.border()
         }
+    }
 
-        if packet.Results.Orders.is_some() {
-            let mut current_orders = packet.Results.Orders.unwrap()
-                .into_iter()
-                .collect::<Vec<(String, crate::model::Order)>>();
+    /// Percent drawdown series, preferring Lean's own and computing it from the equity otherwise
+    pub fn drawdown(&self) -> Vec<(f64, f64)> {
+        if self.lean_drawdown.is_empty() {
+            analytics::drawdown(&self.equity)
+        } else {
+            self.lean_drawdown.clone()
+        }
+    }
 
-            // Sort the keys of the HashMap so that our orders are aligned exactly as they came in
-            current_orders.sort_by(|x, y| x.0.parse::<u64>().unwrap().cmp(&y.0.parse::<u64>().unwrap()));
-
-            &self.order_time.clear();
-            &self.order_type.clear();
-            &self.order_sides.clear();
-            &self.order_qty.clear();
-            &self.order_symbol.clear();
-
-            for (_, order) in current_orders {
-                let (order_time, order_type, direction, quantity, symbol) = order.into_spans();
-
-                &self.order_time.push(order_time);
-                &self.order_type.push(order_type);
-                &self.order_sides.push(direction);
-                &self.order_qty.push(quantity);
-                &self.order_symbol.push(symbol);
-            }   
+    /// Symbols with a price series or a fill, sorted
+    pub fn asset_symbols(&self) -> Vec<String> {
+        let mut symbols = self.assets.keys().cloned().collect::<Vec<String>>();
+        symbols.extend(self.orders.iter().map(|o| o.Symbol.Value.clone()));
+        symbols.sort_unstable();
+        symbols.dedup();
+        symbols
+    }
+
+    /// Whether keys go to the log panel
+    fn logs_active(&self) -> bool {
+        match self.page {
+            Page::Logs => true,
+            Page::Overview => self.focus == Panel::Logs,
+            _ => false
         }
     }
-}
 
-impl<'a> LeftTerminalChunks<'a> {
-    pub fn new(chunks: Vec<tui::layout::Rect>, data: &'a TerminalData) -> Self {
-        Self {
-            graph: chunks[0],
-            logs: chunks[1],
-            data
+    /// Whether the equity chart is on screen
+    fn graph_visible(&self) -> bool {
+        match self.page {
+            Page::Charts => true,
+            Page::Overview => !self.zoomed || self.focus == Panel::Graph,
+            _ => false
         }
     }
 
-    pub fn render_logs(&self, frame: &mut tui::Frame<CrosstermBackend<Stdout>>) {
-        let log_block = Block::default()
-            .title("Algorithm Logs")
-            .borders(Borders::ALL);
+    /// Whether chart keys should go to the equity chart, which the drawdown panel follows
+    fn graph_active(&self) -> bool {
+        self.page == Page::Charts || (self.page == Page::Overview && matches!(self.focus, Panel::Graph | Panel::Drawdown))
+    }
 
-        let logs = self.data.logs
-            .iter()
-            .rev()
-            .take(self.logs.height as usize - 2)
-            .rev()
-            .map(|v| v.clone())
-            .collect::<Vec<ListItem>>();
+    fn input(&mut self, event: Event) {
+        if let Event::Key(KeyEvent { code, .. }) = event {
+            if self.logs_active() && self.log_view.key(code, &self.logs) {
+                return
+            }
+            if self.graph_active() && self.equity_view.key(code, &self.equity) {
+                return
+            }
+            if self.page == Page::Errors && self.error_view.key(code, self.errors.len()) {
+                return
+            }
+            if self.page == Page::Orders && self.order_key(code) {
+                return
+            }
+            if self.page == Page::Stats && self.histogram.key(code) {
+                return
+            }
+            if self.page == Page::Charts && self.rolling.key(code) {
+                return
+            }
+        }
 
-        let log_widget = List::new(logs)
-            .block(log_block);
+        match event {
+            Event::Key(KeyEvent { code: KeyCode::Char('b'), .. }) if self.graph_active() => self.show_benchmark = !self.show_benchmark,
+            Event::Key(KeyEvent { code: KeyCode::Char('t'), .. }) if self.graph_active() => self.show_trades = !self.show_trades,
+            Event::Key(KeyEvent { code: KeyCode::Char('a'), .. }) if self.page == Page::Charts => self.asset += 1,
+            Event::Key(KeyEvent { code: KeyCode::Char('m'), .. }) if self.page == Page::Trades => {
+                self.trade_matching = self.trade_matching.next();
+                self.trades = portfolio::round_trips(&self.fills, self.trade_matching);
+            },
+            Event::Key(KeyEvent { code: KeyCode::Char('s'), .. }) if self.page == Page::Portfolio => self.attribution_sort = self.attribution_sort.next(),
+            Event::Key(KeyEvent { code: KeyCode::Char('r'), .. }) if self.page == Page::Portfolio => self.attribution_descending = !self.attribution_descending,
+            Event::Key(KeyEvent { code: KeyCode::Char('Y'), .. }) => self.yank(),
+            Event::Key(KeyEvent { code: KeyCode::Char('X'), .. }) => {
+                let notice = match self.export() {
+                    Ok(paths) => format!("Exported {} files to {}", paths.len(), self.export_directory.display()),
+                    Err(err) => format!("Export failed: {}", err)
+                };
+                self.notice = Some((notice, Instant::now()));
+            },
+            Event::Key(KeyEvent { code: KeyCode::Char('z'), .. }) if self.page == Page::Overview => self.zoomed = !self.zoomed,
+            Event::Key(KeyEvent { code: KeyCode::Esc, .. }) => self.zoomed = false,
+            Event::Key(KeyEvent { code: KeyCode::Tab, .. }) => self.focus = self.focus.next(),
+            Event::Key(KeyEvent { code: KeyCode::BackTab, .. }) => self.focus = self.focus.previous(),
+            Event::Key(KeyEvent { code: KeyCode::Char(c), .. }) => {
+                if let Some(page) = c.to_digit(10).and_then(Page::from_number) {
+                    self.page = page;
+                }
+            },
+            Event::Mouse(mouse @ MouseEvent::Down(MouseButton::Left, column, row, _)) if !self.is_zoomed() => {
+                // The tab bar always sits on the first row inside the top border
+                let tabs_area = Rect::new(1, 1, u16::MAX - 1, 1);
+                if row == tabs_area.top() {
+                    if let Some(page) = Page::at(column, tabs_area) {
+                        self.page = page;
+                    }
+                } else if self.graph_visible() && self.equity_view.mouse(mouse, &self.equity) {
+                    self.focus = Panel::Graph;
+                }
+            },
+            Event::Mouse(mouse) if self.graph_visible() => {
+                self.equity_view.mouse(mouse, &self.equity);
+            },
+            _ => {}
+        }
+    }
 
-        frame.render_widget(log_widget, self.logs);
+    /// Writes the orders and every chart series as CSV into the export directory
+    pub fn export(&self) -> std::io::Result<Vec<PathBuf>> {
+        export::export(&self.export_directory, &self.orders, &self.charts)
     }
 
-    pub fn render_graph(&self, frame: &mut tui::Frame<CrosstermBackend<Stdout>>) {
-        let graph_block = Block::default()
-            .title("Backtest Performance")
-            .borders(Borders::ALL);
+    /// Moves the order selection, returning false if the key isn't one of ours
+    fn order_key(&mut self, code: KeyCode) -> bool {
+        let last = self.orders.len().saturating_sub(1);
+        let selected = self.order_selected.unwrap_or(last);
+        self.order_selected = match code {
+            KeyCode::Up => Some(selected.saturating_sub(1)),
+            KeyCode::Down if selected + 1 >= last => None,
+            KeyCode::Down => Some(selected + 1),
+            KeyCode::Home => Some(0),
+            KeyCode::End => None,
+            _ => return false
+        };
 
-        if self.data.equity.len() == 0 {
-            frame.render_widget(graph_block, self.graph);
-            return
+        true
+    }
+
+    /// Copies what's selected on the current page to the system clipboard, and says what
+    /// in the tab bar
+    fn yank(&mut self) {
+        let notice = match self.yank_text() {
+            Some((text, what)) => match clipboard::copy(&text) {
+                Ok(()) => format!("Copied {}", what),
+                Err(err) => format!("Copy failed: {}", err)
+            },
+            None => String::from("Nothing to copy here")
+        };
+
+        self.notice = Some((notice, Instant::now()));
+    }
+
+    /// Text the yank key copies on the current page, and a description of it. Tables are
+    /// copied as tab-separated values so they paste into spreadsheets.
+    fn yank_text(&self) -> Option<(String, &'static str)> {
+        if self.logs_active() {
+            // The selected search match, otherwise the bottom line of the panel
+            let lines = self.log_view.lines(&self.logs);
+            let bottom = (self.log_view.first_visible(lines.len()) + self.log_view.height.get()).min(lines.len());
+            let line = lines.get(self.log_view.current.unwrap_or_else(|| bottom.saturating_sub(1)))?;
+            let record = self.logs.get(line.first);
+            let text = match record.algorithm_time {
+                Some(_) => format!("{} : {}", record.time(), record.message),
+                None => record.message
+            };
+            return Some((text, "log line"))
+        }
+
+        if self.page == Page::Orders || (self.page == Page::Overview && self.focus == Panel::Orders) {
+            let order = self.orders.get(self.order_selected.unwrap_or_else(|| self.orders.len().saturating_sub(1)))?;
+            let text = format!(
+                "Id\tTime\tSymbol\tType\tDirection\tQuantity\tPrice\tStatus\tValue\tTag\n{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                order.Id,
+                order.Time,
+                order.Symbol.Value,
+                OrderType::from(order.Type),
+                direction_name(order.Direction),
+                order.Quantity,
+                order.Price,
+                OrderStatus::from(order.Status),
+                order.Value,
+                order.Tag.clone().unwrap_or_default()
+            );
+            return Some((text, "order"))
+        }
+
+        let statistics = match (self.page, self.focus) {
+            (Page::Stats, _) => Some(self.statistics.iter().chain(self.runtime_statistics.iter()).collect::<Vec<_>>()),
+            (Page::Overview, Panel::Metrics) => Some(self.statistics.iter().collect()),
+            (Page::Overview, Panel::Performance) => Some(self.runtime_statistics.iter().collect()),
+            _ => None
+        };
+        if let Some(statistics) = statistics {
+            let text = statistics
+                .iter()
+                .map(|(name, value)| format!("{}\t{}", name, value))
+                .collect::<Vec<String>>()
+                .join("\n");
+            return Some((text, "statistics"))
         }
 
-        let equity = &self.data.equity;
-        let x_axis_bounds = [equity[0].0, equity[equity.len() - 1].0];
-        let y_axis_bounds = [
-            equity.iter().map(|(_, y)| y).fold(f64::INFINITY, |a, &b| a.min(b)),
-            equity.iter().map(|(_, y)| y).fold(0f64, |a, &b| a.max(b))
-        ];
-
-        let graph_widget = Chart::new(vec![
-        Dataset::default()
-            .name("Equity Curve")
-            .graph_type(GraphType::Line)
-            .marker(Marker::Dot)
-            .style(Style::default().fg(Color::White))
-            .data(&equity)])
-        .block(graph_block)
-        .x_axis(Axis::default()
-            .title("Time")
-            .bounds(x_axis_bounds))
-        .y_axis(Axis::default()
-            .title("Equity")
-            .bounds(y_axis_bounds));
-
-        frame.render_widget(graph_widget, self.graph);
+        if self.graph_active() {
+            // The point under the crosshair, otherwise every visible point
+            let points = match self.equity_view.cursor {
+                Some(i) => self.equity.get(i..=i)?,
+                None => self.equity_view.visible(&self.equity)
+            };
+            let mut text = String::from("Time\tEquity\tBenchmark");
+            for (x, y) in points {
+                let benchmark = analytics::value_at(&self.benchmark, *x).map(|b| b.to_string()).unwrap_or_default();
+                text.push_str(&format!("\n{}\t{}\t{}", format_timestamp(*x, "%Y-%m-%d %H:%M:%S"), y, benchmark));
+            }
+            return Some((text, if points.len() == 1 { "chart point" } else { "chart data" }))
+        }
+
+        None
     }
-}
 
-impl<'a> RightTerminalChunk<'a> {
-    pub fn new(chunks: Vec<tui::layout::Rect>, order_chunk: Vec<tui::layout::Rect>, data: &'a TerminalData) -> Self {
-        Self {
-            orders: OrdersChunk::new(order_chunk),
-            performance: chunks[1],
-            metrics: chunks[2],
-            data
+    fn log(&mut self, msg: String, level: LogLevel) {
+        let received = chrono::Utc::now();
+        let records = msg
+            .lines()
+            .map(|line| LogRecord::parse(line, level, received))
+            .collect::<Vec<LogRecord>>();
+
+        let mut actions = vec![];
+        let mut highlights = vec![];
+        for record in records.iter() {
+            let (highlight, record_actions) = self.log_rules.apply(&record.message);
+            highlights.push(highlight);
+            actions.extend(record_actions);
+        }
+
+        if actions.contains(&RuleAction::Bell) {
+            let mut stdout = stdout();
+            let _ = stdout.write_all(b"\x07").and_then(|_| stdout.flush());
+        }
+        if actions.contains(&RuleAction::Pause) {
+            // Lines from here on arrive below the view
+            let lines = self.log_view.lines(&self.logs).len();
+            self.log_view.pause(lines);
+        }
+
+        let pinned = actions.contains(&RuleAction::Pin);
+        if pinned || errors::is_error(&msg, level) {
+            match self.errors.last_mut() {
+                // Stack traces sent on their own belong to the error before them
+                Some(last) if errors::is_trace(&msg) && !pinned => last.trace.extend(msg.lines().map(|line| line.trim_end().to_string())),
+                _ => {
+                    self.errors.push(ErrorEntry::parse(&msg, received));
+                    self.last_error = Some(Instant::now());
+                }
+            }
+        }
+
+        for (record, highlight) in records.into_iter().zip(highlights) {
+            let dropped = self.logs.push(LogRecord { highlight, ..record });
+            self.log_view.shift(&dropped);
         }
     }
-}
 
-impl OrdersChunk {
-    pub fn new(chunk: Vec<tui::layout::Rect>) -> Self {
-        Self {
-            order_time: chunk[0],
-            order_type: chunk[1],
-            order_direction: chunk[2],
-            order_symbol: chunk[3], 
-            order_quantity: chunk[4]
+    /// Writes the report, if one is configured. Errors go to a file since the terminal is
+    /// about to be torn down.
+    fn stop(&self) {
+        let drawdown = self.drawdown();
+        let report = Report {
+            metadata: &self.metadata,
+            statistics: &self.statistics,
+            runtime_statistics: &self.runtime_statistics,
+            orders: &self.orders,
+            equity: &self.equity,
+            benchmark: &self.benchmark,
+            drawdown: &drawdown,
+            currency: &self.currency_symbol()
+        };
+
+        if let Err(err) = report.write(self.report.format, &self.report.path) {
+            std::fs::write("lean_tui_report_error.log", format!("{}: {:?}", self.report.path, err)).unwrap();
         }
     }
-}
 
-impl<'a> TerminalRenderer for LeftTerminalChunks<'a> {
-    fn render(&self, frame: &mut tui::Frame<CrosstermBackend<Stdout>>) {            
-        self.render_logs(frame);
-        self.render_graph(frame);
+    fn packet(&mut self, packet: crate::model::BacktestResultPacket) {
+        self.metadata = Metadata::new(&packet);
+
+        if let Some(packet_charts) = packet.Results.Charts {
+            if let Some(points) = packet_charts.get("Strategy Equity").map(|v| v.Series.get("Equity").unwrap()) {
+                merge_points(&mut self.equity, points, true);
+            };
+
+            if let Some(points) = packet_charts.get("Benchmark").and_then(|v| v.Series.get("Benchmark")) {
+                merge_points(&mut self.benchmark, points, true);
+            }
+
+            if let Some(points) = packet_charts.get("Drawdown").and_then(|v| v.Series.get("Equity Drawdown")) {
+                merge_points(&mut self.lean_drawdown, points, false);
+            }
+
+            if let Some(chart) = packet_charts.get("Assets") {
+                for (symbol, points) in chart.Series.iter() {
+                    merge_points(self.assets.entry(symbol.clone()).or_default(), points, true);
+                }
+            }
+
+            for (name, chart) in packet_charts.iter() {
+                let chart_series = self.charts.entry(name.clone()).or_default();
+                for (series_name, points) in chart.Series.iter() {
+                    merge_points(chart_series.entry(series_name.clone()).or_default(), points, false);
+                }
+            }
+        }
+
+        if let Some(orders) = packet.Results.Orders {
+            let mut current_orders = orders
+                .into_iter()
+                .collect::<Vec<(String, Order)>>();
+
+            // Sort the keys of the HashMap so that our orders are aligned exactly as they came in
+            current_orders.sort_by_key(|x| x.0.parse::<u64>().unwrap());
+
+            self.orders = current_orders
+                .into_iter()
+                .map(|(_, order)| order)
+                .collect();
+        }
+
+        // Events only come once, so new ones are added to the ones we've seen
+        if let Some(events) = packet.Results.OrderEvents {
+            for event in events {
+                if self.order_event_ids.insert((event.OrderId, event.EventId)) {
+                    self.order_events.push(event);
+                }
+            }
+        }
+
+        self.fills = portfolio::fills(&self.orders, &self.order_events);
+        self.positions = portfolio::positions(&self.fills, &self.assets);
+        self.trades = portfolio::round_trips(&self.fills, self.trade_matching);
+
+        if let Some(currency) = packet.Results.AlgorithmConfiguration.and_then(|c| c.AccountCurrency) {
+            self.account_currency = currency;
+        }
+
+        if let Some(statistics) = packet.Results.Statistics {
+            self.statistics = sorted_statistics(statistics);
+        }
+
+        if let Some(runtime_statistics) = packet.Results.RuntimeStatistics {
+            self.runtime_statistics = sorted_statistics(runtime_statistics);
+        }
     }
 }
 
-impl<'a> TerminalRenderer for RightTerminalChunk<'a> {
-    fn render(&self, frame: &mut tui::Frame<CrosstermBackend<Stdout>>) {
-        let orders_time_block = Block::default()
-            .title("Time")
-            .borders(Borders::ALL);
-        let orders_type_block = Block::default()
-            .title("Type")
-            .borders(Borders::ALL);
-        let orders_direction_block = Block::default()
-            .title("Direction")
-            .borders(Borders::ALL);
-        let orders_symbol_block = Block::default()
-            .title("Symbol")
-            .borders(Borders::ALL);
-        let orders_qty_block = Block::default()
-            .title("Quantity")
-            .borders(Borders::ALL);
-
-        let widget_orders_time = List::new(self.data.order_time.iter().rev().take(self.orders.order_time.height as usize - 2).rev().map(|s| ListItem::new(s.clone())).collect::<Vec<ListItem>>()).block(orders_time_block);
-        let widget_orders_type = List::new(self.data.order_type.iter().rev().take(self.orders.order_type.height as usize - 2).rev().map(|s| ListItem::new(s.clone())).collect::<Vec<ListItem>>()).block(orders_type_block);
-        let widget_orders_direction = List::new(self.data.order_sides.iter().rev().take(self.orders.order_direction.height as usize - 2).rev().map(|s| ListItem::new(s.clone())).collect::<Vec<ListItem>>()).block(orders_direction_block);
-        let widget_orders_symbol = List::new(self.data.order_symbol.iter().rev().take(self.orders.order_symbol.height as usize - 2).rev().map(|s| ListItem::new(s.clone())).collect::<Vec<ListItem>>()).block(orders_symbol_block);
-        let widget_orders_qty = List::new(self.data.order_qty.iter().rev().take(self.orders.order_quantity.height as usize - 2).rev().map(|s| ListItem::new(s.clone())).collect::<Vec<ListItem>>()).block(orders_qty_block);
-
-        frame.render_widget(widget_orders_time, self.orders.order_time);
-        frame.render_widget(widget_orders_type, self.orders.order_type);
-        frame.render_widget(widget_orders_direction, self.orders.order_direction);
-        frame.render_widget(widget_orders_symbol, self.orders.order_symbol);
-        frame.render_widget(widget_orders_qty, self.orders.order_quantity);
-    }
-}
\ No newline at end of file
+/// Lean only sends the chart points added since the last packet, so new points are merged
+/// into what we already have, keeping the series sorted by time
+fn merge_points(target: &mut Vec<(f64, f64)>, series: &crate::model::Series, positive_only: bool) {
+    let new_points = series.Values
+        .iter()
+        .map(|xy| (xy.x, xy.y))
+        .filter(|(_, y)| !positive_only || y > &0f64);
+
+    target.extend(new_points);
+    target.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    target.dedup();
+}
+
+fn sorted_statistics(statistics: std::collections::HashMap<String, String>) -> Vec<(String, String)> {
+    let mut statistics = statistics.into_iter().collect::<Vec<(String, String)>>();
+    statistics.sort();
+    statistics
+}
@@ -0,0 +1,276 @@
+use serde::Deserialize;
+use tui::style::{Color, Modifier, Style};
+
+/// Name of a built-in color scheme, as written in the config file
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemePreset {
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+    ColorblindSafe
+}
+
+/// Colors shared by every renderer. Nothing outside of this module should name a `Color` directly.
+#[derive(Clone, Debug)]
+pub struct Theme {
+    /// Regular text
+    pub text: Color,
+    /// Panel borders and titles
+    pub border: Color,
+    /// Selected tab, row or panel
+    pub highlight: Color,
+    /// Strategy equity line
+    pub equity: Color,
+    /// Benchmark line drawn over the equity
+    pub benchmark: Color,
+    /// Underwater curve of the drawdown panel
+    pub drawdown: Color,
+    /// Error logs
+    pub error: Color,
+    /// Buy orders
+    pub buy: Color,
+    /// Sell orders
+    pub sell: Color,
+    /// Orders with no direction
+    pub hold: Color,
+    /// Heatmap backgrounds for negative returns, from mildest to worst
+    pub losses: [Color; 3],
+    /// Heatmap backgrounds for positive returns, from mildest to best
+    pub gains: [Color; 3],
+    /// Whether the colors of log highlight rules are used, or only their modifiers
+    pub rule_colors: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::preset(ThemePreset::default())
+    }
+}
+
+impl Theme {
+    /// Builds the theme for the configured preset, honoring `NO_COLOR` (https://no-color.org)
+    pub fn from_config(config: &crate::config::Config) -> Self {
+        match std::env::var_os("NO_COLOR") {
+            Some(val) if !val.is_empty() => Self::no_color(),
+            _ => Self::preset(config.theme)
+        }
+    }
+
+    pub fn preset(preset: ThemePreset) -> Self {
+        match preset {
+            ThemePreset::Dark => Self {
+                text: Color::Reset,
+                border: Color::Reset,
+                highlight: Color::Yellow,
+                equity: Color::White,
+                benchmark: Color::Cyan,
+                drawdown: Color::Red,
+                error: Color::Red,
+                buy: Color::Green,
+                sell: Color::Red,
+                hold: Color::White,
+                losses: [Color::Indexed(174), Color::Indexed(167), Color::Indexed(160)],
+                gains: [Color::Indexed(151), Color::Indexed(114), Color::Indexed(34)],
+                rule_colors: true,
+            },
+            // The terminal's default foreground is dark here, so nothing can be drawn in white
+            ThemePreset::Light => Self {
+                text: Color::Reset,
+                border: Color::DarkGray,
+                highlight: Color::Blue,
+                equity: Color::Blue,
+                benchmark: Color::Magenta,
+                drawdown: Color::Red,
+                error: Color::Red,
+                buy: Color::Green,
+                sell: Color::Red,
+                hold: Color::Black,
+                losses: [Color::Indexed(224), Color::Indexed(210), Color::Indexed(196)],
+                gains: [Color::Indexed(194), Color::Indexed(120), Color::Indexed(40)],
+                rule_colors: true,
+            },
+            ThemePreset::HighContrast => Self {
+                text: Color::White,
+                border: Color::White,
+                highlight: Color::LightCyan,
+                equity: Color::LightYellow,
+                benchmark: Color::LightMagenta,
+                drawdown: Color::LightRed,
+                error: Color::LightRed,
+                buy: Color::LightGreen,
+                sell: Color::LightRed,
+                hold: Color::White,
+                losses: [Color::Indexed(210), Color::Indexed(203), Color::Indexed(196)],
+                gains: [Color::Indexed(157), Color::Indexed(120), Color::Indexed(46)],
+                rule_colors: true,
+            },
+            // Okabe-Ito blue/orange/vermillion, which stay distinct under protanopia and deuteranopia
+            ThemePreset::ColorblindSafe => Self {
+                text: Color::Reset,
+                border: Color::Reset,
+                highlight: Color::Indexed(39),
+                equity: Color::Indexed(39),
+                benchmark: Color::Indexed(175),
+                drawdown: Color::Indexed(166),
+                error: Color::Indexed(166),
+                buy: Color::Indexed(33),
+                sell: Color::Indexed(214),
+                hold: Color::Reset,
+                // Orange to blue rather than red to green
+                losses: [Color::Indexed(223), Color::Indexed(215), Color::Indexed(208)],
+                gains: [Color::Indexed(153), Color::Indexed(111), Color::Indexed(33)],
+                rule_colors: true,
+            },
+        }
+    }
+
+    /// Every color falls back to the terminal's own foreground; modifiers are kept
+    pub fn no_color() -> Self {
+        Self {
+            text: Color::Reset,
+            border: Color::Reset,
+            highlight: Color::Reset,
+            equity: Color::Reset,
+            benchmark: Color::Reset,
+            drawdown: Color::Reset,
+            error: Color::Reset,
+            buy: Color::Reset,
+            sell: Color::Reset,
+            hold: Color::Reset,
+            losses: [Color::Reset; 3],
+            gains: [Color::Reset; 3],
+            rule_colors: false,
+        }
+    }
+
+    pub fn text(&self) -> Style {
+        Style::default().fg(self.text)
+    }
+
+    pub fn border(&self) -> Style {
+        Style::default().fg(self.border)
+    }
+
+    /// Secondary text such as timestamps
+    pub fn dimmed(&self) -> Style {
+        self.text().add_modifier(Modifier::DIM)
+    }
+
+    /// Reversed so the selection is still visible without color
+    pub fn highlight(&self) -> Style {
+        Style::default().fg(self.highlight).add_modifier(Modifier::BOLD | Modifier::REVERSED)
+    }
+
+    /// Errors stay bold so they can be told apart from regular logs without color
+    pub fn error(&self) -> Style {
+        Style::default().fg(self.error).add_modifier(Modifier::BOLD)
+    }
+
+    /// Heatmap cell for a return, shaded by its size relative to `scale`, the largest
+    /// magnitude shown next to it
+    pub fn heat(&self, value: f64, scale: f64) -> Style {
+        let shades = if value < 0.0 { &self.losses } else { &self.gains };
+        let shade = if scale > 0.0 { ((value.abs() / scale) * 3.0).min(2.0) as usize } else { 0 };
+
+        match shades[shade] {
+            Color::Reset => self.text(),
+            color => Style::default().bg(color).fg(Color::Black)
+        }
+    }
+
+    /// Profit or loss amount, using the strongest shade of the heatmap scale
+    pub fn pnl(&self, value: f64) -> Style {
+        let color = if value < 0.0 { self.losses[2] } else { self.gains[2] };
+        Style::default().fg(color)
+    }
+
+    /// Style of a log highlight rule. Colors are dropped under `NO_COLOR` and unknown names
+    /// are ignored, leaving the regular text style.
+    pub fn rule(&self, color: Option<&str>, background: Option<&str>, bold: bool) -> Style {
+        let mut style = self.text();
+        if self.rule_colors {
+            if let Some(color) = color.and_then(parse_color) {
+                style = style.fg(color);
+            }
+            if let Some(color) = background.and_then(parse_color) {
+                style = style.bg(color);
+            }
+        }
+        if bold {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+
+        style
+    }
+}
+
+/// Parses a color name such as `light-red`, a palette index or `#rrggbb`
+fn parse_color(name: &str) -> Option<Color> {
+    let name = name.trim().to_ascii_lowercase().replace('_', "-");
+    if let Some(hex) = name.strip_prefix('#') {
+        let channel = |i: usize| hex.get(i..i + 2).and_then(|c| u8::from_str_radix(c, 16).ok());
+        return if hex.len() == 6 { Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?)) } else { None }
+    }
+    if let Ok(index) = name.parse::<u8>() {
+        return Some(Color::Indexed(index))
+    }
+
+    let color = match name.as_str() {
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "dark-gray" | "dark-grey" => Color::DarkGray,
+        "light-red" => Color::LightRed,
+        "light-green" => Color::LightGreen,
+        "light-yellow" => Color::LightYellow,
+        "light-blue" => Color::LightBlue,
+        "light-magenta" => Color::LightMagenta,
+        "light-cyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => return None
+    };
+
+    Some(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_color_names_indices_and_hex() {
+        assert_eq!(parse_color("red"), Some(Color::Red));
+        assert_eq!(parse_color(" Light_Blue "), Some(Color::LightBlue));
+        assert_eq!(parse_color("dark-grey"), Some(Color::DarkGray));
+        assert_eq!(parse_color("200"), Some(Color::Indexed(200)));
+        assert_eq!(parse_color("#ff8000"), Some(Color::Rgb(255, 128, 0)));
+        assert_eq!(parse_color("#FF8000"), Some(Color::Rgb(255, 128, 0)));
+    }
+
+    #[test]
+    fn parse_color_rejects_invalid() {
+        assert_eq!(parse_color("purple"), None);
+        assert_eq!(parse_color(""), None);
+        assert_eq!(parse_color("256"), None);
+        assert_eq!(parse_color("#fff"), None);
+        assert_eq!(parse_color("#gg0000"), None);
+        assert_eq!(parse_color("#ff80001"), None);
+    }
+
+    #[test]
+    fn rule_style_without_color() {
+        let theme = Theme::preset(ThemePreset::Dark);
+        assert_eq!(theme.rule(Some("red"), Some("#000000"), true), theme.text().fg(Color::Red).bg(Color::Rgb(0, 0, 0)).add_modifier(Modifier::BOLD));
+        assert_eq!(theme.rule(Some("purple"), None, false), theme.text());
+
+        let theme = Theme::no_color();
+        assert_eq!(theme.rule(Some("red"), None, true), theme.text().add_modifier(Modifier::BOLD));
+    }
+}
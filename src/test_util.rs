@@ -0,0 +1,6 @@
+//! Helpers shared by the unit tests
+
+/// Asserts that two floats are equal up to rounding
+pub fn assert_close(actual: f64, expected: f64) {
+    assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+}